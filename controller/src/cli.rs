@@ -3,6 +3,8 @@ use client::{start_client, ToGUI, ClientState};
 
 use cube_model as cube;
 use cube_model::Cube;
+use cube_model::catalogue;
+use game_timer::MAX_INSPECTION;
 
 use std::str;
 use std::process::Command;
@@ -14,6 +16,9 @@ use std::fs::File;
 
 use serde::{Deserialize, Serialize};
 
+// Frames shown part way through a twist by the anim command, the whole twist takes 120ms however many there are
+const ANIMATION_FRAMES: usize = 8;
+
 struct TermCols{
     white:String
     ,red:String
@@ -220,7 +225,10 @@ fn main() {
                                 sender.send(DetectInputs())?;
                             }
                             ,"start" => {
-                                sender.send(StartGame())?;
+                                sender.send(StartGame(None))?;
+                            }
+                            ,"new session" => {
+                                sender.send(ResetSession())?;
                             }
                             ,"exit" => {
                                 sender.send(ShutDown())?;
@@ -242,18 +250,15 @@ fn main() {
                                         match t{
                                             Err(_) => { println!("bad argument"); }
                                             ,Ok(t) => {
-                                                let anim = data.cube.twist(t);
+                                                let mut anim = [Cube::new(); ANIMATION_FRAMES];
+                                                data.cube.twist_frames(t, &mut anim);
                                                 let oldcube = data.cube;
-                                                let ms = 30;
-                                                data.cube = anim[0];
-                                                draw(&gfx, &data);
-                                                thread::sleep(std::time::Duration::from_millis(ms));
-                                                data.cube = anim[1];
-                                                draw(&gfx, &data);
-                                                thread::sleep(std::time::Duration::from_millis(ms));
-                                                data.cube = anim[2];
-                                                draw(&gfx, &data);
-                                                thread::sleep(std::time::Duration::from_millis(ms));
+                                                let ms = 120 / (ANIMATION_FRAMES as u64 + 1);
+                                                for frame in anim{
+                                                    data.cube = frame;
+                                                    draw(&gfx, &data);
+                                                    thread::sleep(std::time::Duration::from_millis(ms));
+                                                }
                                                 data.cube = oldcube;
                                                 draw(&gfx, &data);
                                                 thread::sleep(std::time::Duration::from_millis(ms));
@@ -287,6 +292,28 @@ fn main() {
                                             }
                                         }
                                     }
+                                    ,"facelets" => {
+                                        // With no parameter, show the state in the standard formats for other cube software
+                                        if args.is_empty(){
+                                            let data = state.lock().unwrap();
+                                            println!("{}", data.cube.facelets());
+                                            println!("{}", data.cube.serialise_standard());
+                                        }
+                                        else{
+                                            let cube: Result<Cube, &str> = Cube::from_facelets(args[0]);
+                                            match cube{
+                                                Err(msg) => {println!("Error: {}", msg)}
+                                                ,Ok(cube) => {sender.send(SetState(cube))?;}
+                                            }
+                                        }
+                                    }
+                                    ,"start" => {
+                                        // Start with a different length of inspection, 0 for none
+                                        match args.first().map(|s| u64::from_str(s)) {
+                                            Some(Ok(secs)) if args.len() == 1 && secs <= MAX_INSPECTION.as_secs() => {sender.send(StartGame(Some(secs)))?;}
+                                            ,_ => {println!("start takes an optional parameter, the number of seconds of inspection (0 for none, at most {})", MAX_INSPECTION.as_secs());}
+                                        }
+                                    }
                                     ,"brightness" => {
                                         if args.len() != 1{
                                             println!("brightness requires one parameter, a number in the range 0 to 255");
@@ -295,6 +322,23 @@ fn main() {
                                             sender.send(SetBrightness(args[0].to_string()))?;
                                         }
                                     }
+                                    ,"pattern" => {
+                                        // With no parameter, list the names that can be used
+                                        if args.is_empty(){
+                                            for entry in &catalogue::CATALOGUE{
+                                                println!("{}: {}", entry.name, entry.twists);
+                                            }
+                                        }
+                                        else if args.len() > 2 || (args.len() == 2 && args[1] != "animate"){
+                                            println!("pattern requires the name of a pattern or algorithm, optionally followed by animate");
+                                        }
+                                        else{
+                                            match catalogue::lookup(args[0]){
+                                                None => {println!("Unknown pattern: {}", args[0]);}
+                                                ,Some(entry) => {sender.send(ShowPattern(entry.name.to_string(), args.len() == 2))?;}
+                                            }
+                                        }
+                                    }
                                     ,_ => {println!("Unknown command: {}",cmd);}
                                 }
                             }
@@ -1,11 +1,13 @@
 use cube_model as cube;
 use cube::{Cube, Output, OutputMap5Faces, Twist};
+use cube::solver::Solver;
+use cube::scramble::scramble;
 
 use game_timer::TimerState;
+use game_timer::stats::Summary;
 
 use std::str;
 use std::time::{Instant,Duration};
-use rand::Rng;
 use std::io::{Read,Write,BufRead,BufReader};
 use std::net::TcpStream;
 use std::sync::mpsc::{channel,Sender,Receiver};
@@ -202,6 +204,9 @@ pub struct ClientState {
     ,pub last_timer_update: Instant
     ,pub timer_state: TimerState
     ,pub record_time: u128
+    // When each stage of the current solve was done, in the same order as Stage::ALL
+    ,pub splits: [Option<Duration>; 4]
+    ,pub session: Summary
 }
 
 impl ClientState {
@@ -213,6 +218,8 @@ impl ClientState {
             ,last_timer_update: Instant::now()
             ,timer_state: TimerState::default()
             ,record_time: 0
+            ,splits: [None; 4]
+            ,session: Summary::default()
         }
     }
 }
@@ -247,7 +254,7 @@ pub enum FromGUI {
     Connect(Vec<u8>, String) // secret, address
     ,DetectLEDs()
     ,DetectInputs()
-    ,StartGame()
+    ,StartGame(Option<u64>) // inspection seconds, None for the usual 15
     ,SetState(Cube)
     ,GetState()
     ,SyncState()
@@ -256,6 +263,8 @@ pub enum FromGUI {
     ,ShutDown()
     ,SetBrightness(String)
     ,CancelTimer()
+    ,ShowPattern(String, bool) // name, animate
+    ,ResetSession()
 }
 
 impl FromGUI{
@@ -331,6 +340,19 @@ impl<T: Read + Write, C: Connector<Stream=T>> Messenger<T, C>{
     }
 }
 
+// The solver's tables are cached in the user's own cache directory, without one they're generated every time
+fn load_solver() -> Solver {
+    match dirs_next::cache_dir() {
+        Some(dir) => {
+            let dir = dir.join("giant_led_cube");
+            // If the directory can't be made, saving the tables fails and they're generated again next time
+            let _ignored = std::fs::create_dir_all(&dir);
+            Solver::load_or_generate(dir.join("solver_tables"))
+        }
+        ,None => Solver::generate()
+    }
+}
+
 pub fn start_client() -> (Arc<Mutex<ClientState>>, Sender<FromGUI>, Receiver<ToGUI>, JoinHandle<()>) {
     let state = Arc::new(Mutex::new(ClientState::new()));
     let gui_state = Arc::clone(&state);
@@ -358,6 +380,9 @@ pub fn start_client() -> (Arc<Mutex<ClientState>>, Sender<FromGUI>, Receiver<ToG
     let thread = thread::spawn(move||{
         let mut command_queue: VecDeque<(String, Vec<String>)> = VecDeque::new();
         let mut got_challenge = false;
+        // The solver's tables take a while to load or generate, so start on them in the background straight away
+        let mut solver_loading = Some(thread::spawn(load_solver));
+        let mut solver: Option<Solver> = None;
 
         let mut net_thread: Option<JoinHandle<()>> = None;
         let mut timeout_time: Arc<Mutex<Option<Instant>>> = Arc::new(Mutex::new(None));
@@ -517,17 +542,35 @@ pub fn start_client() -> (Arc<Mutex<ClientState>>, Sender<FromGUI>, Receiver<ToG
                                         }
                                         ,"timer_state" => {
                                             let now = Instant::now();
-                                            if args.len() >= 3{
+                                            if args.len() >= 5{
                                                 let mut state = state.lock().unwrap();
                                                 state.last_timer_update = now;
                                                 if let Ok(new_time) = TimerState::deserialise_now_ish(
-                                                    (args[0].clone(), args[1].clone(), args[2].clone())
+                                                    (args[0].clone(), args[1].clone(), args[2].clone(), args[3].clone(), args[4].clone())
                                                 ){
                                                     state.timer_state = new_time;
                                                 }
                                                 to_gui_sender.send(ToGUI::StateUpdate());
                                             }
                                         }
+                                        ,"splits" => {
+                                            if args.len() >= 4{
+                                                if let Ok(splits) = TimerState::deserialise_splits(
+                                                    (args[0].clone(), args[1].clone(), args[2].clone(), args[3].clone())
+                                                ){
+                                                    let mut state = state.lock().unwrap();
+                                                    state.splits = splits;
+                                                }
+                                                to_gui_sender.send(ToGUI::StateUpdate());
+                                            }
+                                        }
+                                        ,"session_stats" => {
+                                            if let Ok(session) = Summary::deserialise(&args) {
+                                                let mut state = state.lock().unwrap();
+                                                state.session = session;
+                                                to_gui_sender.send(ToGUI::StateUpdate());
+                                            }
+                                        }
                                         ,"record_time" => {
                                             if args.len() >= 1 {
                                                 let time: u128 = args[0].parse().unwrap_or(0);
@@ -620,30 +663,21 @@ pub fn start_client() -> (Arc<Mutex<ClientState>>, Sender<FromGUI>, Receiver<ToG
                                 command_queue.push_back(("set_state".to_string(), vec![test_state]));
                                 to_gui_sender.send(ToGUI::StateUpdate())?;
                             }
-                            ,StartGame() => {
-                                let mut state = state.lock().unwrap();
-                                state.cube = cube_model::Cube::new();
-                                let mut last_twist = Twist::from_string("F").unwrap();
-                                let mut twist;
-                                let mut rng = rand::rngs::OsRng;
-                                // A very naive scramble algorithm
-                                for _ in 0..30{
-                                //for _ in 0..1{ // for testing purposes
-                                    loop {
-                                        twist = Twist{
-                                            face: rng.gen_range(0..6)
-                                            ,reverse: rng.gen_bool(0.5)
-                                        };
-                                        if !(twist.face == last_twist.face) && (twist.reverse != last_twist.reverse) {
-                                            break
-                                        }
-                                    }
-                                    last_twist = twist;
-                                    state.cube.twist(twist);
+                            ,StartGame(inspection) => {
+                                // The scramble is worked out before locking the state, so the GUI doesn't stop while the solver loads
+                                if solver.is_none() {
+                                    solver = solver_loading.take().and_then(|t| t.join().ok());
+                                }
+                                let solver = solver.get_or_insert_with(load_solver);
+                                let mut cube = cube_model::Cube::new();
+                                for twist in scramble(solver, None){
+                                    cube.twist(twist);
                                 }
+                                let mut state = state.lock().unwrap();
+                                state.cube = cube;
                                 command_queue.push_back(("set_state".to_string(), vec![state.cube.serialise()]));
                                 command_queue.push_back(("play".to_string(), vec![]));
-                                command_queue.push_back(("timed_start".to_string(), vec![]));
+                                command_queue.push_back(("timed_start".to_string(), inspection.iter().map(|s| s.to_string()).collect()));
                                 to_gui_sender.send(ToGUI::StateUpdate())?;
                             }
                             ,CancelTimer() => {
@@ -667,6 +701,19 @@ pub fn start_client() -> (Arc<Mutex<ClientState>>, Sender<FromGUI>, Receiver<ToG
                             ,SetBrightness(b) => {
                                 command_queue.push_back(("set_brightness".to_string(), vec![b]));
                             }
+                            ,ShowPattern(name, animate) => {
+                                let mut args = vec![name];
+                                if animate {
+                                    args.push("animate".to_string());
+                                }
+                                command_queue.push_back(("pattern".to_string(), args));
+                                // Algorithms are done from the current state, so only the service knows where the cube ends up
+                                command_queue.push_back(("get_state".to_string(), vec![]));
+                            }
+                            ,ResetSession() => {
+                                // The service replies with the empty session stats
+                                command_queue.push_back(("reset_session".to_string(), vec![]));
+                            }
                         }
                     }
                 }
@@ -22,6 +22,8 @@ use gla::UniformVec2;
 
 pub mod client;
 use client::{start_client, ToGUI, FromGUI, ClientState};
+use game_timer::{Penalty, Stage};
+use game_timer::stats::SolveTime;
 
 use cube_model as cube;
 use cube::Cube;
@@ -229,6 +231,7 @@ struct DataModel{
     ,diff: f32
     ,frames: i32
     ,brightness: u8
+    ,inspection_seconds: Option<u64>
 }
 
 
@@ -438,6 +441,7 @@ fn init_render_data(start_fullscreen: bool, start_w: u32,start_h: u32) -> Render
 
         let scramble_button = Button::new(left + 10.0, 240.0, 540.0,110.0, "Scramble".to_string(), "scramble".to_string(), 80.0);
         let end_button = Button::new(left + 10.0, 100.0, 540.0,110.0, "Reset Cube".to_string(), "reset".to_string(), 80.0);
+        let session_button = Button::new(left + 10.0, -40.0, 540.0,110.0, "New Session".to_string(), "new_session".to_string(), 80.0);
 
         let b_minus = Button::new(left + 370.0, -240.0, 80.0,80.0, "−".to_string(), "b-".to_string(), 50.0);
         let b_plus = Button::new(left + 470.0, -240.0, 80.0,80.0, "+".to_string(), "b+".to_string(), 50.0);
@@ -460,7 +464,7 @@ fn init_render_data(start_fullscreen: bool, start_w: u32,start_h: u32) -> Render
             ,texture: texture
             ,cur: PhysicalPosition{x:0.0,y:0.0}
             ,s_cur: PhysicalPosition{x:0.0,y:0.0}
-            ,buttons: RefCell::new(vec![scramble_button, end_button, b_plus, b_minus])
+            ,buttons: RefCell::new(vec![scramble_button, end_button, session_button, b_plus, b_minus])
             ,pressed: false
             ,released: false
             ,font_cache: RefCell::new(GlyphSheet::new(tex_size))
@@ -624,7 +628,7 @@ const B_STEP: u8 = 10;
 const MIN_BRIGHTNESS: u8 = 105;
 const DEFAULT_BRIGHTNESS: u8 = 255;
 
-fn ui_loop(mut gfx: RenderData, state: Arc<Mutex<ClientState>>, sender: Sender<FromGUI>, receiver: Receiver<ToGUI>){
+fn ui_loop(mut gfx: RenderData, state: Arc<Mutex<ClientState>>, sender: Sender<FromGUI>, receiver: Receiver<ToGUI>, inspection_seconds: Option<u64>){
 
     let mut data = DataModel{
         d:0.0
@@ -632,6 +636,7 @@ fn ui_loop(mut gfx: RenderData, state: Arc<Mutex<ClientState>>, sender: Sender<F
         ,diff: 0.0
         ,frames:0
         ,brightness: DEFAULT_BRIGHTNESS
+        ,inspection_seconds
     };
 
     fn update(data: &mut DataModel){
@@ -744,13 +749,21 @@ fn ui_loop(mut gfx: RenderData, state: Arc<Mutex<ClientState>>, sender: Sender<F
                     "Ready to start".to_string()
                 }
                 else if state.timer_state.is_inspecting(Some(now)) {
-                    format!("Inspection: {}s", 15 - state.timer_state.inspection_so_far(Some(now)).as_secs())
+                    match state.timer_state.inspection_time().as_secs().checked_sub(state.timer_state.inspection_so_far(Some(now)).as_secs()) {
+                        Some(left) => format!("Inspection: {}s", left)
+                        // Starting now is a two second penalty
+                        ,None => "Inspection: +2".to_string()
+                    }
                 }
                 else{
                     if state.timer_state.is_ended() {
                         let flash = (data.frames % 30) > 10;
                         if flash {
-                            format_time(state.timer_state.solve_so_far())
+                            match state.timer_state.penalty() {
+                                Some(Penalty::Dnf) => "DNF".to_string()
+                                ,Some(Penalty::PlusTwo) => format!("{} +2", format_time(state.timer_state.solve_so_far()))
+                                ,_ => format_time(state.timer_state.solve_so_far())
+                            }
                         }
                         else {
                             "".to_string()
@@ -760,7 +773,24 @@ fn ui_loop(mut gfx: RenderData, state: Arc<Mutex<ClientState>>, sender: Sender<F
                         format_time(state.timer_state.solve_so_far())
                     }
                 };
+                let splits: Vec<String> = Stage::ALL.iter().zip(state.splits).map(|(stage, split)|
+                    format!("{}: {}", stage.name(), split.map(format_time).unwrap_or("-".to_string()))
+                ).collect();
+                let splits = splits.join("\n");
+                let stat = |t: Option<SolveTime>| match t {
+                    Some(SolveTime::Time(t)) => format_time(t)
+                    ,Some(SolveTime::Dnf) => "DNF".to_string()
+                    ,None => "-".to_string()
+                };
+                let session = format!("Session: {} solves\nBest: {}\nWorst: {}\nmo3: {}\nao5: {}\nao12: {}",
+                    state.session.count, stat(state.session.best), stat(state.session.worst), stat(state.session.mo3), stat(state.session.ao5), stat(state.session.ao12));
                 black_text(gfx, &timer_msg, -1920.0/2.0, (-1080.0/2.0)+250.0, 170.0);
+                if state.splits.iter().any(|s| s.is_some()) {
+                    black_text(gfx, &splits, 1920.0/2.0 - 500.0, (-1080.0/2.0)+350.0, 60.0);
+                }
+                if state.session.count > 0 {
+                    black_text(gfx, &session, 1920.0/2.0 - 500.0, 150.0, 50.0);
+                }
                 if state.record_time > 0 {
                     black_text(gfx, &format!("Current\nRecord:\n{}", format_time(Duration::from_millis(state.record_time.try_into().unwrap_or(0)))), 1920.0/2.0 - 500.0, 1080.0/2.0, 100.0);
                 }
@@ -793,12 +823,15 @@ fn ui_loop(mut gfx: RenderData, state: Arc<Mutex<ClientState>>, sender: Sender<F
                         use client::FromGUI::*;
                         match button.id.as_ref(){
                             "scramble" => {
-                                sender.send(StartGame());
+                                sender.send(StartGame(data.inspection_seconds));
                             }
                             ,"reset" => {
                                 sender.send(CancelTimer());
                                 sender.send(SetState(Cube::new()));
                             }
+                            ,"new_session" => {
+                                sender.send(ResetSession());
+                            }
                             ,"b+" => {
                                 if data.brightness >= MAX_BRIGHTNESS - B_STEP {
                                     data.brightness = MAX_BRIGHTNESS;
@@ -926,6 +959,8 @@ struct GuiConfig{
     ,start_fullscreen: bool
     ,start_width: u32
     ,start_height: u32
+    // Seconds of inspection for games started from the GUI, 0 for none, leave out for the usual 15
+    ,inspection_seconds: Option<u64>
 }
 
 fn main() {
@@ -949,5 +984,5 @@ fn main() {
     let addr = config.server;
 
     sender.send(client::FromGUI::Connect(secret, addr));
-    ui_loop(gfx, state, sender, receiver);
+    ui_loop(gfx, state, sender, receiver, config.inspection_seconds);
 }
@@ -0,0 +1,395 @@
+// Beginner's method (layer by layer) solver, for teaching
+//
+// The bottom layer is solved first, then the middle layer, then the top layer, using only the few
+// algorithms that a beginner would learn. The solutions are far longer than the ones from the two-phase
+// solver, but they are split into stages so that they can be taught one step at a time.
+// The cube is never rotated, the bottom layer is whichever colour has its centre on the bottom.
+
+use crate::{Cube, Twist, TOP, FRONT, LEFT, BACK, RIGHT, BOTTOM};
+use crate::cubie::{CubieCube, CORNER_FACELETS};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stage{
+    Cross
+    ,FirstLayerCorners
+    ,MiddleLayer
+    ,LastLayerCross
+    ,LastLayerEdges
+    ,LastLayerCornerPositions
+    ,LastLayerCornerOrientations
+}
+
+pub const STAGES: [Stage; 7] = [
+    Stage::Cross
+    ,Stage::FirstLayerCorners
+    ,Stage::MiddleLayer
+    ,Stage::LastLayerCross
+    ,Stage::LastLayerEdges
+    ,Stage::LastLayerCornerPositions
+    ,Stage::LastLayerCornerOrientations
+];
+
+impl Stage{
+    pub fn name(&self) -> &'static str{
+        match self {
+            Stage::Cross => "Cross"
+            ,Stage::FirstLayerCorners => "First layer corners"
+            ,Stage::MiddleLayer => "Middle layer"
+            ,Stage::LastLayerCross => "Last layer cross"
+            ,Stage::LastLayerEdges => "Last layer edges"
+            ,Stage::LastLayerCornerPositions => "Position last layer corners"
+            ,Stage::LastLayerCornerOrientations => "Orient last layer corners"
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step{
+    pub stage: Stage
+    ,pub twists: Vec<Twist>
+}
+
+// The algorithms are written as seen from the front, on_side turns them to face the other sides
+const CORNER_OUT: &str = "R U R'";
+const CORNER_IN: &str = "R U R' U'";
+const EDGE_IN_RIGHT: &str = "U R U' R' U' F' U F";
+const EDGE_IN_LEFT: &str = "U' L' U L U F U' F'";
+const LAST_LAYER_CROSS: &str = "F R U R' U' F'";
+const SUNE: &str = "R U R' U R U2 R'";
+const NIKLAS: &str = "U R U' L' U R' U' L";
+const CORNER_TWIST: &str = "R' D' R D";
+
+// Each side's right hand neighbour is the next one along
+const SIDES: [usize; 4] = [FRONT, RIGHT, BACK, LEFT];
+
+const CROSS_EDGES: [usize; 4] = [4, 5, 6, 7]; // DR, DF, DL, DB
+const FIRST_LAYER_CORNERS: [usize; 4] = [4, 5, 6, 7]; // DFR, DLF, DBL, DRB
+const MIDDLE_EDGES: [usize; 4] = [8, 9, 10, 11]; // FR, FL, BL, BR
+const LAST_LAYER: [usize; 4] = [0, 1, 2, 3];
+
+fn alg(s: &str) -> Vec<Twist>{
+    Twist::seq_from_string(s).expect("Bad built in algorithm")
+}
+
+// The algorithm done while looking at SIDES[side] instead of the front
+fn on_side(s: &str, side: usize) -> Vec<Twist>{
+    alg(s).into_iter().map(|t|{
+        match SIDES.iter().position(|f| *f == t.face) {
+            Some(i) => Twist{face: SIDES[(i + side) % 4], ..t}
+            ,None => t
+        }
+    }).collect()
+}
+
+fn top_turns() -> Vec<Vec<Twist>>{
+    (1..4).filter_map(|turns| Twist::from_quarter_turns(TOP, turns)).map(|t| vec![t]).collect()
+}
+
+fn turn(cubies: &CubieCube, twists: &[Twist]) -> CubieCube{
+    let mut result = *cubies;
+    for t in twists{
+        let face_move = CubieCube::face_move(t.face);
+        for _ in 0..t.quarter_turns(){
+            result = result.multiply(&face_move);
+        }
+    }
+    result
+}
+
+// Index of the corner position whose facelets are on exactly these faces
+fn corner_at(faces: [usize; 3]) -> usize{
+    CORNER_FACELETS.iter().position(|c| faces.iter().all(|f| c.iter().any(|(face, _)| face == f))).unwrap()
+}
+
+fn corners_solved(c: &CubieCube, corners: &[usize]) -> bool{
+    corners.iter().all(|&i| c.cp[i] as usize == i && c.co[i] == 0)
+}
+
+fn edges_solved(c: &CubieCube, edges: &[usize]) -> bool{
+    edges.iter().all(|&i| c.ep[i] as usize == i && c.eo[i] == 0)
+}
+
+fn first_two_layers_solved(c: &CubieCube) -> bool{
+    edges_solved(c, &CROSS_EDGES) && corners_solved(c, &FIRST_LAYER_CORNERS) && edges_solved(c, &MIDDLE_EDGES)
+}
+
+// Tries every combination of up to max_depth of the algorithms, returns the shortest one that reaches the goal
+fn search(start: &CubieCube, algs: &[Vec<Twist>], max_depth: usize, goal: impl Fn(&CubieCube) -> bool) -> Option<Vec<Twist>>{
+    if goal(start) {
+        return Some(vec![]);
+    }
+    let mut level = vec![(*start, vec![])];
+    for _ in 0..max_depth{
+        let mut best: Option<Vec<Twist>> = None;
+        let mut next = Vec::with_capacity(level.len() * algs.len());
+        for (cubies, twists) in &level{
+            for a in algs{
+                let cubies = turn(cubies, a);
+                let mut twists = twists.clone();
+                twists.extend_from_slice(a);
+                if goal(&cubies) && best.as_ref().is_none_or(|b| twists.len() < b.len()) {
+                    best = Some(twists.clone());
+                }
+                next.push((cubies, twists));
+            }
+        }
+        if best.is_some() {
+            return best;
+        }
+        level = next;
+    }
+    None
+}
+
+struct Progress{
+    cubies: CubieCube
+    ,twists: Vec<Twist>
+    ,steps: Vec<Step>
+}
+
+impl Progress{
+    fn apply(&mut self, twists: &[Twist]){
+        self.cubies = turn(&self.cubies, twists);
+        for t in twists{
+            // Turning the same face twice in a row is written as one twist
+            match self.twists.last() {
+                Some(last) if last.face == t.face => {
+                    let combined = Twist::from_quarter_turns(t.face, last.quarter_turns() + t.quarter_turns());
+                    self.twists.pop();
+                    self.twists.extend(combined);
+                }
+                _ => self.twists.push(*t)
+            }
+        }
+    }
+
+    fn apply_search(&mut self, algs: &[Vec<Twist>], max_depth: usize, goal: impl Fn(&CubieCube) -> bool) -> Result<(), &'static str>{
+        let twists = search(&self.cubies, algs, max_depth, goal).ok_or("The beginner solver got stuck")?;
+        self.apply(&twists);
+        Ok(())
+    }
+
+    fn finish(&mut self, stage: Stage){
+        let twists = std::mem::take(&mut self.twists);
+        self.steps.push(Step{stage, twists});
+    }
+}
+
+// The number of moves needed to solve the given cross edges, from a breadth first search over where
+// those edges are. Each edge is encoded as position * 2 + orientation, the index is those digits in base 24
+fn cross_distances(edges: &[usize], moves: &[CubieCube]) -> Vec<u8>{
+    let mut edge_moves = vec![[0; 24]; moves.len()];
+    for (move_cube, edge_move) in moves.iter().zip(edge_moves.iter_mut()){
+        for (code, moved) in edge_move.iter_mut().enumerate(){
+            let (pos, ori) = (code / 2, code % 2);
+            let to = move_cube.ep.iter().position(|&e| e as usize == pos).unwrap();
+            *moved = to * 2 + (ori ^ move_cube.eo[to] as usize);
+        }
+    }
+    let size = 24usize.pow(edges.len() as u32);
+    let mut dist = vec![u8::MAX; size];
+    let solved = edges.iter().rev().fold(0, |index, e| index * 24 + e * 2);
+    dist[solved] = 0;
+    let mut queue = vec![solved];
+    let mut next = 0;
+    while next < queue.len(){
+        let index = queue[next];
+        next += 1;
+        for edge_move in &edge_moves{
+            let mut moved = 0;
+            let mut rest = index;
+            let mut scale = 1;
+            for _ in edges{
+                moved += edge_move[rest % 24] * scale;
+                rest /= 24;
+                scale *= 24;
+            }
+            if dist[moved] == u8::MAX {
+                dist[moved] = dist[index] + 1;
+                queue.push(moved);
+            }
+        }
+    }
+    dist
+}
+
+fn cross_index(c: &CubieCube, edges: &[usize]) -> usize{
+    edges.iter().rev().fold(0, |index, &e|{
+        let pos = c.ep.iter().position(|&p| p as usize == e).unwrap();
+        index * 24 + pos * 2 + c.eo[pos] as usize
+    })
+}
+
+// One edge at a time, each one in as few moves as possible without breaking the ones before
+fn solve_cross(p: &mut Progress){
+    let twists: Vec<Twist> = [TOP, FRONT, LEFT, BACK, RIGHT, BOTTOM].iter()
+        .flat_map(|&face| (1..4).filter_map(move |turns| Twist::from_quarter_turns(face, turns)))
+        .collect();
+    let moves: Vec<CubieCube> = twists.iter().map(|t| turn(&CubieCube::SOLVED, &[*t])).collect();
+    for n in 1..=CROSS_EDGES.len(){
+        let edges = &CROSS_EDGES[0..n];
+        let dist = cross_distances(edges, &moves);
+        while dist[cross_index(&p.cubies, edges)] > 0{
+            let here = dist[cross_index(&p.cubies, edges)];
+            let t = twists.iter().find(|t| dist[cross_index(&turn(&p.cubies, &[**t]), edges)] < here).unwrap();
+            p.apply(&[*t]);
+        }
+    }
+}
+
+fn solve_first_layer_corners(p: &mut Progress) -> Result<(), &'static str>{
+    for (i, &corner) in FIRST_LAYER_CORNERS.iter().enumerate(){
+        if corners_solved(&p.cubies, &[corner]) {
+            continue;
+        }
+        // If it is in the wrong place in the bottom layer, take it out into the top layer first
+        let pos = p.cubies.cp.iter().position(|&c| c as usize == corner).unwrap();
+        if let Some(side) = (0..4).find(|&s| corner_at([BOTTOM, SIDES[s], SIDES[(s + 1) % 4]]) == pos) {
+            p.apply(&on_side(CORNER_OUT, side));
+        }
+        // Turn the top until it is above where it goes, then repeat the insert until it is solved
+        let side = (0..4).find(|&s| corner_at([BOTTOM, SIDES[s], SIDES[(s + 1) % 4]]) == corner).unwrap();
+        let above = corner_at([TOP, SIDES[side], SIDES[(side + 1) % 4]]);
+        let turns = (0..4).find(|&u| {
+            let c = turn(&p.cubies, &Twist::from_quarter_turns(TOP, u).into_iter().collect::<Vec<_>>());
+            c.cp[above] as usize == corner
+        }).ok_or("The beginner solver got stuck")?;
+        p.apply(&Twist::from_quarter_turns(TOP, turns).into_iter().collect::<Vec<_>>());
+        for _ in 0..6{
+            if corners_solved(&p.cubies, &[corner]) {
+                break;
+            }
+            p.apply(&on_side(CORNER_IN, side));
+        }
+        if !corners_solved(&p.cubies, &FIRST_LAYER_CORNERS[0..=i]) || !edges_solved(&p.cubies, &CROSS_EDGES) {
+            return Err("The beginner solver got stuck");
+        }
+    }
+    Ok(())
+}
+
+fn solve_middle_layer(p: &mut Progress) -> Result<(), &'static str>{
+    let mut algs = top_turns();
+    for side in 0..4{
+        algs.push(on_side(EDGE_IN_RIGHT, side));
+        algs.push(on_side(EDGE_IN_LEFT, side));
+    }
+    for i in 0..MIDDLE_EDGES.len(){
+        // An edge in the wrong slot takes three steps: push it out, turn the top, insert it
+        p.apply_search(&algs, 3, |c| {
+            edges_solved(c, &CROSS_EDGES) && corners_solved(c, &FIRST_LAYER_CORNERS) && edges_solved(c, &MIDDLE_EDGES[0..=i])
+        })?;
+    }
+    Ok(())
+}
+
+fn last_layer_edges_solved(c: &CubieCube) -> bool{
+    first_two_layers_solved(c) && edges_solved(c, &LAST_LAYER)
+}
+
+fn solve_last_layer_corner_orientations(p: &mut Progress) -> Result<(), &'static str>{
+    // Only the top is turned, to bring each twisted corner to the front right in turn.
+    // The bottom two layers come back together once every corner has been twisted
+    let front_right = corner_at([TOP, FRONT, RIGHT]);
+    for _ in 0..LAST_LAYER.len(){
+        let Some(turns) = (0..4).find(|&u| {
+            let c = turn(&p.cubies, &Twist::from_quarter_turns(TOP, u).into_iter().collect::<Vec<_>>());
+            c.co[front_right] != 0
+        }) else {
+            break;
+        };
+        p.apply(&Twist::from_quarter_turns(TOP, turns).into_iter().collect::<Vec<_>>());
+        // A bottom corner passes through with its bottom colour on top, so check that the top corner is back too
+        for _ in 0..6{
+            if p.cubies.co[front_right] == 0 && LAST_LAYER.contains(&(p.cubies.cp[front_right] as usize)) {
+                break;
+            }
+            p.apply(&alg(CORNER_TWIST));
+        }
+    }
+    p.apply_search(&top_turns(), 1, |c| c.is_solved())
+}
+
+// Solves the cube one stage at a time, there is a step for every stage even if it needs no twists
+pub fn solve(cube: &Cube) -> Result<Vec<Step>, &'static str>{
+    let cubies = CubieCube::validated(cube).map_err(|e| e.message())?;
+    let mut p = Progress{cubies, twists: vec![], steps: vec![]};
+
+    solve_cross(&mut p);
+    p.finish(Stage::Cross);
+
+    solve_first_layer_corners(&mut p)?;
+    p.finish(Stage::FirstLayerCorners);
+
+    solve_middle_layer(&mut p)?;
+    p.finish(Stage::MiddleLayer);
+
+    let algs: Vec<Vec<Twist>> = (0..4).map(|side| on_side(LAST_LAYER_CROSS, side)).collect();
+    p.apply_search(&algs, 3, |c| first_two_layers_solved(c) && LAST_LAYER.iter().all(|&i| c.eo[i] == 0))?;
+    p.finish(Stage::LastLayerCross);
+
+    let mut algs: Vec<Vec<Twist>> = (0..4).map(|side| on_side(SUNE, side)).collect();
+    algs.extend(top_turns());
+    p.apply_search(&algs, 4, last_layer_edges_solved)?;
+    p.finish(Stage::LastLayerEdges);
+
+    let algs: Vec<Vec<Twist>> = (0..4).map(|side| on_side(NIKLAS, side)).collect();
+    p.apply_search(&algs, 2, |c| last_layer_edges_solved(c) && LAST_LAYER.iter().all(|&i| c.cp[i] as usize == i))?;
+    p.finish(Stage::LastLayerCornerPositions);
+
+    solve_last_layer_corner_orientations(&mut p)?;
+    p.finish(Stage::LastLayerCornerOrientations);
+
+    Ok(p.steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Cube;
+    use super::{solve, Stage, STAGES};
+
+    fn check_solves(scramble: &str){
+        let mut cube = Cube::new();
+        cube.twists(scramble).unwrap();
+        let steps = solve(&cube).unwrap();
+        assert_eq!(steps.iter().map(|s| s.stage).collect::<Vec<_>>(), STAGES.to_vec());
+        for step in steps{
+            for t in step.twists{
+                cube.twist(t);
+            }
+            if step.stage == Stage::FirstLayerCorners {
+                let bottom = &cube.serialise()[45..54];
+                assert!(bottom.chars().all(|c| bottom.starts_with(c)), "bottom not solved for {}", scramble);
+            }
+        }
+        assert!(cube.is_solved(), "failed to solve {}", scramble);
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn solves() {
+        let steps = solve(&Cube::new()).unwrap();
+        assert!(steps.iter().all(|s| s.twists.is_empty()));
+        check_solves("R");
+        check_solves("R U R' U' F2 D L' B");
+        check_solves("D' R2 B2 L2 F2 D2 U' L2 U' F' R' U2 B' F' L2 B' U F' D' U'");
+        check_solves("U R2 F B R B2 R U2 L B2 R U' D' R2 F R' L B2 U2 F2");
+        check_solves("L2 U' B2 D' R2 U L2 F2 U' B' R F' D2 L' U B' L D' F' R'");
+        check_solves("M E S x R U y' F2 M2 D");
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn stage_names() {
+        assert_eq!(Stage::Cross.name(), "Cross");
+        assert_eq!(Stage::LastLayerCornerOrientations.name(), "Orient last layer corners");
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn bad_state() {
+        let mut cube = Cube::new();
+        cube.deserialise("WWWWWWWWBRRWRRRRRRGGGGGGGGGOOOOOOOOORBBBBBBBBYYYYYYYYY").unwrap();
+        assert!(solve(&cube).is_err());
+    }
+}
@@ -0,0 +1,140 @@
+// Named patterns and algorithms, for showing off and for practising
+//
+// A pattern is a state to put the cube in, made by doing its twists on a solved cube. An algorithm is done from
+// whatever state the cube is in. Twists are in standard notation with no brackets, so they can be checked and done
+// without std, and names only use letters and underscores so they can be sent as command arguments
+
+use crate::{Cube, Notation};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind{
+    Pattern
+    ,Algorithm
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Entry{
+    pub name: &'static str
+    ,pub kind: Kind
+    ,pub twists: &'static str
+}
+
+impl Entry{
+    // The state that the pattern makes, or the state the algorithm leaves a solved cube in
+    pub fn state(&self) -> Cube{
+        let mut cube = Cube::new();
+        cube.twists_in(self.twists, Notation::Standard).expect("catalogue twists are valid");
+        cube
+    }
+}
+
+const fn pattern(name: &'static str, twists: &'static str) -> Entry{
+    Entry{name, kind: Kind::Pattern, twists}
+}
+
+const fn algorithm(name: &'static str, twists: &'static str) -> Entry{
+    Entry{name, kind: Kind::Algorithm, twists}
+}
+
+pub const CATALOGUE: [Entry; 22] = [
+    pattern("checkerboard", "M2 E2 S2")
+    ,pattern("superflip", "U R2 F B R B2 R U2 L B2 R U' D' R2 F R' L B2 U2 F2")
+    ,pattern("cube_in_cube", "F L F U' R U F2 L2 U' L' B D' B' L2 U")
+    ,pattern("cube_in_cube_in_cube", "U' L' U' F' R2 B' R F U B2 U B' L U' F U R F'")
+    ,pattern("six_spots", "U D' R L' F B' U D'")
+    ,pattern("plus_minus", "U2 R2 L2 U2 R2 L2")
+    ,pattern("tetris", "L R F B U' D' L' R'")
+    ,pattern("wire", "R L F B R L F B R L F B R2 B2 L2 R2 B2 L2")
+    ,pattern("anaconda", "L U B' U' R L' B R' F B' D R D' F'")
+    ,algorithm("sexy_move", "R U R' U'")
+    ,algorithm("sledgehammer", "R' F R F'")
+    ,algorithm("sune", "R U R' U R U2 R'")
+    ,algorithm("antisune", "R U2 R' U' R U' R'")
+    ,algorithm("t_perm", "R U R' U' R' F R2 U' R' U' R U R' F'")
+    ,algorithm("y_perm", "F R U' R' U' R U R' F' R U R' U' R' F R F'")
+    ,algorithm("ja_perm", "L' U' L F L' U' L U L F' L2 U L")
+    ,algorithm("jb_perm", "R U R' F' R U R' U' R' F R2 U' R'")
+    ,algorithm("ua_perm", "M2 U M U2 M' U M2")
+    ,algorithm("ub_perm", "M2 U' M U2 M' U' M2")
+    ,algorithm("h_perm", "M2 U M2 U2 M2 U M2")
+    ,algorithm("z_perm", "M' U M2 U M2 U M' U2 M2")
+    ,algorithm("aa_perm", "R' F R' B2 R F' R' B2 R2")
+];
+
+// Finds an entry by name, ignoring case
+pub fn lookup(name: &str) -> Option<&'static Entry>{
+    CATALOGUE.iter().find(|e| e.name.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Cube, BOTTOM};
+    use crate::cubie::CubieCube;
+    use crate::cfop::{self, Pll};
+    use super::{CATALOGUE, Kind, lookup};
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn lookups() {
+        assert_eq!(lookup("T_Perm").unwrap().kind, Kind::Algorithm);
+        assert_eq!(lookup("checkerboard").unwrap().twists, "M2 E2 S2");
+        assert!(lookup("t perm").is_none());
+        for (i, e) in CATALOGUE.iter().enumerate(){
+            assert!(e.name.chars().all(|c| c.is_ascii_lowercase() || c == '_'), "{}", e.name);
+            assert!(!CATALOGUE[i+1..].iter().any(|other| other.name == e.name), "{}", e.name);
+            assert!(!e.state().is_solved(), "{}", e.name);
+            assert!(e.state().validate().is_ok(), "{}", e.name);
+        }
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn patterns() {
+        // Corners and centres stay put, edges go to the opposite face
+        let checkerboard = lookup("checkerboard").unwrap().state();
+        for face in &checkerboard.faces{
+            for s in 0..9{
+                assert_eq!(face.subfaces[s].color == face.subfaces[4].color, s % 2 == 0);
+            }
+        }
+        // Every edge flipped in place
+        let superflip = CubieCube::from_cube(&lookup("superflip").unwrap().state()).unwrap();
+        assert_eq!(superflip.cp, CubieCube::SOLVED.cp);
+        assert_eq!(superflip.co, CubieCube::SOLVED.co);
+        assert_eq!(superflip.ep, CubieCube::SOLVED.ep);
+        assert!(superflip.eo.iter().all(|o| *o == 1));
+        // The centres stay put, with the rest of each face all one other colour around them
+        let spots = lookup("six_spots").unwrap().state();
+        for face in &spots.faces{
+            let outside = face.subfaces[0].color;
+            assert!((0..9).all(|s| (face.subfaces[s].color == outside) == (s != 4)));
+        }
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn algorithms() {
+        // Last layer algorithms leave the first two layers alone
+        for e in CATALOGUE.iter().filter(|e| e.name.ends_with("perm") || e.name.ends_with("sune")){
+            assert_eq!(cfop::f2l_pairs(&e.state(), BOTTOM), 4, "{}", e.name);
+        }
+        let pll = |name: &str| cfop::pll_case(&lookup(name).unwrap().state(), BOTTOM);
+        assert_eq!(pll("t_perm"), Some(Pll::T));
+        assert_eq!(pll("y_perm"), Some(Pll::Y));
+        assert_eq!(pll("h_perm"), Some(Pll::H));
+        assert_eq!(pll("z_perm"), Some(Pll::Z));
+        // Doing an algorithm on a solved cube gives the case its inverse solves
+        assert_eq!(pll("ja_perm"), Some(Pll::Ja));
+        assert_eq!(pll("ua_perm"), Some(Pll::Ub));
+        assert_eq!(pll("aa_perm"), Some(Pll::Ab));
+        let oll = |name: &str| cfop::oll_case(&lookup(name).unwrap().state(), BOTTOM);
+        assert_eq!(oll("sune"), Some(26));
+        assert_eq!(oll("antisune"), Some(27));
+        // Six sexy moves get back to where they started
+        let mut cube = Cube::new();
+        for _ in 0..6{
+            cube.twists(lookup("sexy_move").unwrap().twists).unwrap();
+        }
+        assert!(cube.is_solved());
+    }
+}
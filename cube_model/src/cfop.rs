@@ -0,0 +1,397 @@
+// Recognising how far through a CFOP solve a cube is: cross, first two layers, OLL and PLL
+//
+// Everything works on whichever face has the cross, the cube is turned so that face is on the bottom first.
+// The OLL and PLL cases are found by comparing the last layer with the states that each case's
+// algorithm solves, allowing for the last layer being turned (AUF) and for looking at it from any side
+
+use std::sync::OnceLock;
+
+use crate::{Cube, Colors, Twist, TOP, FRONT, LEFT, BACK, RIGHT};
+use crate::cubie::{CORNER_FACELETS, EDGE_FACELETS};
+use crate::sequence::Sequence;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pll{
+    // Nothing left to do, except maybe a U turn
+    Solved
+    ,Aa, Ab, E, F, Ga, Gb, Gc, Gd, H, Ja, Jb, Na, Nb, Ra, Rb, T, Ua, Ub, V, Y, Z
+}
+
+impl Pll{
+    pub fn name(&self) -> &'static str{
+        match self {
+            Pll::Solved => "Solved"
+            ,Pll::Aa => "Aa" ,Pll::Ab => "Ab" ,Pll::E => "E" ,Pll::F => "F"
+            ,Pll::Ga => "Ga" ,Pll::Gb => "Gb" ,Pll::Gc => "Gc" ,Pll::Gd => "Gd"
+            ,Pll::H => "H" ,Pll::Ja => "Ja" ,Pll::Jb => "Jb" ,Pll::Na => "Na" ,Pll::Nb => "Nb"
+            ,Pll::Ra => "Ra" ,Pll::Rb => "Rb" ,Pll::T => "T" ,Pll::Ua => "Ua" ,Pll::Ub => "Ub"
+            ,Pll::V => "V" ,Pll::Y => "Y" ,Pll::Z => "Z"
+        }
+    }
+}
+
+// How far through the solve the cube is. With no cross everything else is empty
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Progress{
+    pub cross: Option<usize>
+    ,pub f2l_pairs: usize
+    // Only known once the first two layers are done
+    ,pub oll: Option<u8>
+    // Only known once the last layer is oriented
+    ,pub pll: Option<Pll>
+}
+
+// One algorithm for each OLL case, in the standard order
+const OLL_ALGORITHMS: [&str; 57] = [
+    "R U2 R2 F R F' U2 R' F R F'"
+    ,"F R U R' U' F' f R U R' U' f'"
+    ,"f R U R' U' f' U' F R U R' U' F'"
+    ,"f R U R' U' f' U F R U R' U' F'"
+    ,"r' U2 R U R' U r"
+    ,"r U2 R' U' R U' r'"
+    ,"r U R' U R U2 r'"
+    ,"l' U' L U' L' U2 l"
+    ,"R U R' U' R' F R2 U R' U' F'"
+    ,"R U R' U R' F R F' R U2 R'"
+    ,"r U R' U R' F R F' R U2 r'"
+    ,"M' R' U' R U' R' U2 R U' R r'"
+    ,"F U R U' R2 F' R U R U' R'"
+    ,"R' F R U R' F' R F U' F'"
+    ,"l' U' l L' U' L U l' U l"
+    ,"r U r' R U R' U' r U' r'"
+    ,"F R' F' R2 r' U R U' R' U' M'"
+    ,"r U R' U R U2 r2 U' R U' R' U2 r"
+    ,"r' R U R U R' U' M' R' F R F'"
+    ,"r U R' U' M2 U R U' R' U' M'"
+    ,"R U2 R' U' R U R' U' R U' R'"
+    ,"R U2 R2 U' R2 U' R2 U2 R"
+    ,"R2 D' R U2 R' D R U2 R"
+    ,"r U R' U' r' F R F'"
+    ,"F' r U R' U' r' F R"
+    ,"R U2 R' U' R U' R'"
+    ,"R U R' U R U2 R'"
+    ,"r U R' U' r' R U R U' R'"
+    ,"R U R' U' R U' R' F' U' F R U R'"
+    ,"F R' F R2 U' R' U' R U R' F2"
+    ,"R' U' F U R U' R' F' R"
+    ,"L U F' U' L' U L F L'"
+    ,"R U R' U' R' F R F'"
+    ,"R U R2 U' R' F R U R U' F'"
+    ,"R U2 R2 F R F' R U2 R'"
+    ,"L' U' L U' L' U L U L F' L' F"
+    ,"F R' F' R U R U' R'"
+    ,"R U R' U R U' R' U' R' F R F'"
+    ,"L F' L' U' L U F U' L'"
+    ,"R' F R U R' U' F' U R"
+    ,"R U R' U R U2 R' F R U R' U' F'"
+    ,"R' U' R U' R' U2 R F R U R' U' F'"
+    ,"F' U' L' U L F"
+    ,"F U R U' R' F'"
+    ,"F R U R' U' F'"
+    ,"R' U' R' F R F' U R"
+    ,"R' U' R' F R F' R' F R F' U R"
+    ,"F R U R' U' R U R' U' F'"
+    ,"r U' r2 U r2 U r2 U' r"
+    ,"r' U r2 U' r2 U' r2 U r'"
+    ,"F U R U' R' U R U' R' F'"
+    ,"R U R' U R U' B U' B' R'"
+    ,"l' U2 L U L' U' L U L' U l"
+    ,"r U2 R' U' R U R' U' R U' r'"
+    ,"R' F R U R U' R2 F' R2 U' R' U R U R'"
+    ,"r' U' r U' R' U R U' R' U R r' U r"
+    ,"R U R' U' M' U R U' r'"
+];
+
+const PLL_ALGORITHMS: [(Pll, &str); 21] = [
+    (Pll::Aa, "x R' U R' D2 R U' R' D2 R2 x'")
+    ,(Pll::Ab, "x R2 D2 R U R' D2 R U' R x'")
+    ,(Pll::E, "x' R U' R' D R U R' D' R U R' D R U' R' D' x")
+    ,(Pll::F, "R' U' F' R U R' U' R' F R2 U' R' U' R U R' U R")
+    ,(Pll::Ga, "R2 U R' U R' U' R U' R2 U' D R' U R D'")
+    ,(Pll::Gb, "R' U' R U D' R2 U R' U R U' R U' R2 D")
+    ,(Pll::Gc, "R2 U' R U' R U R' U R2 U D' R U' R' D")
+    ,(Pll::Gd, "R U R' U' D R2 U' R U' R' U R' U R2 D'")
+    ,(Pll::H, "M2 U M2 U2 M2 U M2")
+    ,(Pll::Ja, "x R2 F R F' R U2 r' U r U2 x'")
+    ,(Pll::Jb, "R U R' F' R U R' U' R' F R2 U' R'")
+    ,(Pll::Na, "R U R' U R U R' F' R U R' U' R' F R2 U' R' U2 R U' R'")
+    ,(Pll::Nb, "R' U R U' R' F' U' F R U R' F R' F' R U' R")
+    ,(Pll::Ra, "R U' R' U' R U R D R' U' R D' R' U2 R'")
+    ,(Pll::Rb, "R2 F R U R U' R' F' R U2 R' U2 R")
+    ,(Pll::T, "R U R' U' R' F R2 U' R' U' R U R' F'")
+    ,(Pll::Ua, "M2 U M U2 M' U M2")
+    ,(Pll::Ub, "M2 U' M U2 M' U' M2")
+    ,(Pll::V, "R' U R' U' y R' F' R2 U' R' U R' F R F")
+    ,(Pll::Y, "F R U' R' U' R U R' F' R U R' U' R' F R F'")
+    ,(Pll::Z, "M' U M2 U M2 U M' U2 M2")
+];
+
+const SIDES: [usize; 4] = [FRONT, RIGHT, BACK, LEFT];
+
+// The corners and edges in each slot of the first two layers when the cross is on the bottom
+const F2L_SLOTS: [(usize, usize); 4] = [(4, 8), (5, 9), (6, 10), (7, 11)];
+
+fn piece_solved(cube: &Cube, facelets: &[(usize, usize)]) -> bool{
+    facelets.iter().all(|&(f, s)| cube.faces[f].subfaces[s].color == cube.faces[f].subfaces[4].color)
+}
+
+fn cross_solved(cube: &Cube, face: usize) -> bool{
+    EDGE_FACELETS.iter().filter(|e| e.iter().any(|(f, _)| *f == face)).all(|e| piece_solved(cube, e))
+}
+
+// Faces that have a solved cross, a solved cube has all six
+pub fn crosses(cube: &Cube) -> Vec<usize>{
+    (0..6).filter(|f| cross_solved(cube, *f)).collect()
+}
+
+// The cube turned over so that `face` is on the bottom
+fn with_face_down(cube: &Cube, face: usize) -> Cube{
+    let rotation = match face {
+        TOP => "x2"
+        ,FRONT => "x'"
+        ,BACK => "x"
+        ,LEFT => "z'"
+        ,RIGHT => "z"
+        // Already on the bottom
+        ,_ => ""
+    };
+    let mut turned = *cube;
+    turned.twists(rotation).expect("Rotations always parse");
+    turned
+}
+
+fn pairs_solved(cube: &Cube) -> usize{
+    F2L_SLOTS.iter().filter(|(c, e)| piece_solved(cube, &CORNER_FACELETS[*c]) && piece_solved(cube, &EDGE_FACELETS[*e])).count()
+}
+
+// Number of solved pairs in the first two layers, around the cross on `face`
+pub fn f2l_pairs(cube: &Cube, face: usize) -> usize{
+    if !cross_solved(cube, face) {
+        return 0;
+    }
+    pairs_solved(&with_face_down(cube, face))
+}
+
+// Which last layer stickers are the colour of the top, the top face and then the top rows of the sides
+fn oll_pattern(cube: &Cube) -> u32{
+    let top = cube.faces[TOP].subfaces[4].color;
+    let stickers = [0, 1, 2, 3, 5, 6, 7, 8].iter().map(|s| (TOP, *s))
+        .chain(SIDES.iter().flat_map(|f| (0..3).map(move |s| (*f, s))));
+    stickers.fold(0, |pattern, (f, s)| (pattern << 1) | (cube.faces[f].subfaces[s].color == top) as u32)
+}
+
+// The same pattern however the last layer is turned
+fn oll_key(cube: &Cube) -> u32{
+    let mut cube = *cube;
+    let mut key = u32::MAX;
+    for _ in 0..4{
+        key = key.min(oll_pattern(&cube));
+        cube.twist(Twist{face: TOP, reverse: false, double: false, wide: false, layer: 0});
+    }
+    key
+}
+
+// For each sticker on the top rows of the sides, how many sides round its colour's centre is from where it is
+fn pll_pattern(cube: &Cube) -> Option<[u8; 12]>{
+    let centres: Vec<Colors> = SIDES.iter().map(|f| cube.faces[*f].subfaces[4].color).collect();
+    let mut pattern = [0; 12];
+    for (i, f) in SIDES.iter().enumerate(){
+        for s in 0..3{
+            let home = centres.iter().position(|c| *c == cube.faces[*f].subfaces[s].color)?;
+            pattern[i * 3 + s] = ((home + 4 - i) % 4) as u8;
+        }
+    }
+    Some(pattern)
+}
+
+// The same pattern however the last layer is turned, and from whichever side it's looked at
+fn pll_key(cube: &Cube) -> Option<[u8; 12]>{
+    let mut cube = *cube;
+    let mut key: Option<[u8; 12]> = None;
+    for _ in 0..4{
+        let pattern = pll_pattern(&cube)?;
+        for side in 0..4{
+            let mut seen_from = pattern;
+            seen_from.rotate_left(side * 3);
+            key = Some(key.map_or(seen_from, |k| k.min(seen_from)));
+        }
+        cube.twist(Twist{face: TOP, reverse: false, double: false, wide: false, layer: 0});
+    }
+    key
+}
+
+// The state that an algorithm solves
+fn case_for(algorithm: &str) -> Cube{
+    let mut cube = Cube::new();
+    let twists: Sequence = algorithm.parse().expect("Bad built in algorithm");
+    for t in twists.inverse().twists{
+        cube.twist(t);
+    }
+    cube
+}
+
+fn oll_cases() -> &'static Vec<(u32, u8)>{
+    static CASES: OnceLock<Vec<(u32, u8)>> = OnceLock::new();
+    CASES.get_or_init(|| {
+        let mut cases = vec![(oll_key(&Cube::new()), 0)];
+        for (i, algorithm) in OLL_ALGORITHMS.iter().enumerate(){
+            cases.push((oll_key(&case_for(algorithm)), i as u8 + 1));
+        }
+        cases
+    })
+}
+
+fn pll_cases() -> &'static Vec<([u8; 12], Pll)>{
+    static CASES: OnceLock<Vec<([u8; 12], Pll)>> = OnceLock::new();
+    CASES.get_or_init(|| {
+        let mut cases = vec![(pll_key(&Cube::new()).expect("Solved cube has a PLL pattern"), Pll::Solved)];
+        for (pll, algorithm) in PLL_ALGORITHMS{
+            cases.push((pll_key(&case_for(algorithm)).expect("PLL cases are oriented"), pll));
+        }
+        cases
+    })
+}
+
+// The OLL case (1 to 57) on the layer opposite the cross on `face`, or 0 if the last layer is already oriented.
+// None if the first two layers aren't done
+pub fn oll_case(cube: &Cube, face: usize) -> Option<u8>{
+    if f2l_pairs(cube, face) < 4 {
+        return None;
+    }
+    let key = oll_key(&with_face_down(cube, face));
+    oll_cases().iter().find(|(k, _)| *k == key).map(|(_, case)| *case)
+}
+
+// The PLL case on the layer opposite the cross on `face`. None if the last layer isn't oriented yet
+pub fn pll_case(cube: &Cube, face: usize) -> Option<Pll>{
+    if oll_case(cube, face) != Some(0) {
+        return None;
+    }
+    let key = pll_key(&with_face_down(cube, face))?;
+    pll_cases().iter().find(|(k, _)| *k == key).map(|(_, case)| *case)
+}
+
+// How far through a solve the cube is, using whichever cross has the most done around it
+pub fn progress(cube: &Cube) -> Progress{
+    let best = crosses(cube).into_iter().max_by_key(|f| (f2l_pairs(cube, *f), oll_case(cube, *f) == Some(0)));
+    match best {
+        None => Progress{cross: None, f2l_pairs: 0, oll: None, pll: None}
+        ,Some(face) => Progress{
+            cross: Some(face)
+            ,f2l_pairs: f2l_pairs(cube, face)
+            ,oll: oll_case(cube, face)
+            ,pll: pll_case(cube, face)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Cube, TOP, FRONT, LEFT, BOTTOM, RIGHT};
+    use super::{crosses, f2l_pairs, oll_case, pll_case, progress, case_for, oll_key, pll_key, with_face_down, Pll, OLL_ALGORITHMS, PLL_ALGORITHMS};
+
+    fn cube(twists: &str) -> Cube{
+        let mut c = Cube::new();
+        c.twists(twists).unwrap();
+        c
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn faces_down() {
+        let solved = Cube::new();
+        for f in 0..6{
+            let turned = with_face_down(&solved, f);
+            assert_eq!(turned.faces[BOTTOM].subfaces[4].color, solved.faces[f].subfaces[4].color);
+        }
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn cross_and_pairs() {
+        assert_eq!(crosses(&Cube::new()), vec![0, 1, 2, 3, 4, 5]);
+        let c = cube("R U R' U'");
+        assert_eq!(crosses(&c), vec![LEFT, BOTTOM]);
+        assert_eq!(f2l_pairs(&c, BOTTOM), 3);
+        assert_eq!(f2l_pairs(&c, TOP), 0);
+        // Turning the whole cube moves the cross with it
+        let c = cube("F R U R' U' F' x");
+        assert_eq!(crosses(&c), vec![FRONT, LEFT]);
+        assert_eq!(f2l_pairs(&c, FRONT), 4);
+        assert_eq!(oll_case(&c, FRONT), Some(44));
+        let c = cube("R U R' U' z D U");
+        assert!(crosses(&c).is_empty());
+        assert_eq!(progress(&c), super::Progress{cross: None, f2l_pairs: 0, oll: None, pll: None});
+        let c = cube("U R U' R' U' F' U F");
+        assert_eq!(f2l_pairs(&c, BOTTOM), 3);
+        assert_eq!(progress(&c).oll, None);
+        let c = cube("F2 D");
+        assert!(crosses(&c).is_empty());
+        let c = cube("R U' R'");
+        assert_eq!(crosses(&c), vec![BOTTOM]);
+        assert_eq!(f2l_pairs(&c, BOTTOM), 3);
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn oll_cases() {
+        // Every case is different, and none of them touch the first two layers
+        let mut keys = vec![oll_key(&Cube::new())];
+        for (i, algorithm) in OLL_ALGORITHMS.iter().enumerate(){
+            let c = case_for(algorithm);
+            assert_eq!(f2l_pairs(&c, BOTTOM), 4, "OLL {} breaks the first two layers", i + 1);
+            assert_eq!(oll_case(&c, BOTTOM), Some(i as u8 + 1));
+            let key = oll_key(&c);
+            assert!(!keys.contains(&key), "OLL {} is the same as another case", i + 1);
+            keys.push(key);
+        }
+        let top_edges = |case: usize| {
+            let c = case_for(OLL_ALGORITHMS[case - 1]);
+            [1, 3, 5, 7].iter().filter(|s| c.faces[TOP].subfaces[**s].color == c.faces[TOP].subfaces[4].color).count()
+        };
+        let top_corners = |case: usize| {
+            let c = case_for(OLL_ALGORITHMS[case - 1]);
+            [0, 2, 6, 8].iter().filter(|s| c.faces[TOP].subfaces[**s].color == c.faces[TOP].subfaces[4].color).count()
+        };
+        for dot in [1, 2, 3, 4, 17, 18, 19, 20]{
+            assert_eq!(top_edges(dot), 0, "OLL {} should be a dot", dot);
+        }
+        for ocll in 21..=27{
+            assert_eq!(top_edges(ocll), 4, "OLL {} should have a cross", ocll);
+        }
+        assert_eq!(top_corners(1), 0);
+        assert_eq!(top_corners(20), 4);
+        assert_eq!(top_corners(26), 1);
+        assert_eq!(top_corners(27), 1);
+        assert_eq!(top_corners(28), 4);
+        assert_eq!(top_corners(57), 4);
+
+        // Any turn of the last layer, and any way the cube is held, is the same case
+        let c = cube("U' F R U R' U' F' U2 y z2");
+        assert_eq!(crosses(&c), vec![TOP, RIGHT]);
+        assert_eq!(oll_case(&c, TOP), Some(44));
+        assert_eq!(oll_case(&cube("R U2 R' U' R U' R' U"), BOTTOM), Some(27));
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn pll_cases() {
+        let mut keys = vec![pll_key(&Cube::new()).unwrap()];
+        for (pll, algorithm) in PLL_ALGORITHMS{
+            let c = case_for(algorithm);
+            assert_eq!(oll_case(&c, BOTTOM), Some(0), "{} disturbs the rest of the cube", pll.name());
+            assert_eq!(pll_case(&c, BOTTOM), Some(pll));
+            let key = pll_key(&c).unwrap();
+            assert!(!keys.contains(&key), "{} is the same as another case", pll.name());
+            keys.push(key);
+        }
+        assert_eq!(pll_case(&Cube::new(), BOTTOM), Some(Pll::Solved));
+        assert_eq!(pll_case(&cube("U"), BOTTOM), Some(Pll::Solved));
+        assert_eq!(pll_case(&cube("U2 R U R' U' R' F R2 U' R' U' R U R' F' U' x2"), TOP), Some(Pll::T));
+        assert_eq!(pll_case(&cube("F R U R' U' F'"), BOTTOM), None);
+        let p = progress(&cube("M2 U M2 U2 M2 U M2 y'"));
+        assert_eq!(p.f2l_pairs, 4);
+        assert_eq!(p.oll, Some(0));
+        assert_eq!(p.pll, Some(Pll::H));
+    }
+}
@@ -0,0 +1,552 @@
+// Corner and edge level view of a cube, this uses the piece numbering from Kociemba's two-phase algorithm
+// Corners: URF, UFL, ULB, UBR, DFR, DLF, DBL, DRB
+// Edges: UR, UF, UL, UB, DR, DF, DL, DB, FR, FL, BL, BR
+//
+// The pieces are numbered relative to wherever the centres are, so outer face turns are just a
+// multiplication, and the cube is solved when every piece is in its own place whichever way round it is.
+
+use core::fmt;
+
+use crate::{Cube, Colors, Twist, OPPOSITE, TOP, FRONT, LEFT, BACK, RIGHT, BOTTOM, FAKE_FACE_MIN, CENTER_FB, CENTER_LR, CENTER_BT, ROTATE_X, ROTATE_Y, ROTATE_Z};
+
+// Reasons that a cube state can't be reached by twisting a solved cube
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StateError{
+    UnknownColour
+    ,ColourCount(Colors, usize)
+    ,ImpossibleCentres
+    ,ImpossibleCorner
+    ,ImpossibleEdge
+    ,DuplicateCorner
+    ,DuplicateEdge
+    ,TwistedCorner
+    ,FlippedEdge
+    ,Parity
+}
+
+impl StateError{
+    pub fn message(&self) -> &'static str{
+        match self {
+            StateError::UnknownColour => "A subface is not one of the six colours"
+            ,StateError::ColourCount(_, _) => "There are not nine subfaces of each colour"
+            ,StateError::ImpossibleCentres => "The centres are not arranged like a real cube"
+            ,StateError::ImpossibleCorner => "A corner has colours that no real corner has"
+            ,StateError::ImpossibleEdge => "An edge has colours that no real edge has"
+            ,StateError::DuplicateCorner => "Two corners are the same"
+            ,StateError::DuplicateEdge => "Two edges are the same"
+            ,StateError::TwistedCorner => "A corner is twisted"
+            ,StateError::FlippedEdge => "An edge is flipped"
+            ,StateError::Parity => "Two pieces are swapped"
+        }
+    }
+
+    // Short name for sending in protocol messages
+    pub fn code(&self) -> &'static str{
+        match self {
+            StateError::UnknownColour => "unknown_colour"
+            ,StateError::ColourCount(_, _) => "colour_count"
+            ,StateError::ImpossibleCentres => "impossible_centres"
+            ,StateError::ImpossibleCorner => "impossible_corner"
+            ,StateError::ImpossibleEdge => "impossible_edge"
+            ,StateError::DuplicateCorner => "duplicate_corner"
+            ,StateError::DuplicateEdge => "duplicate_edge"
+            ,StateError::TwistedCorner => "corner_twist"
+            ,StateError::FlippedEdge => "edge_flip"
+            ,StateError::Parity => "parity"
+        }
+    }
+}
+
+impl fmt::Display for StateError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::ColourCount(color, count) => write!(f, "There are {} {:?} subfaces, there should be 9", count, color)
+            ,e => write!(f, "{}", e.message())
+        }
+    }
+}
+
+const COLORS: [Colors; 6] = [Colors::White, Colors::Red, Colors::Blue, Colors::Green, Colors::Yellow, Colors::Orange];
+
+fn centres(cube: &Cube) -> [Colors; 6]{
+    let mut centres = [Colors::Blank; 6];
+    for (f, centre) in centres.iter_mut().enumerate(){
+        *centre = cube.faces[f].subfaces[4].color;
+    }
+    centres
+}
+
+// For each whole cube rotation, the face whose centre moves to each face
+pub(crate) const ROTATION_CENTRES: [[usize; 6]; 3] = [
+    [FRONT, BOTTOM, LEFT, TOP, RIGHT, BACK]
+    ,[TOP, RIGHT, FRONT, LEFT, BACK, BOTTOM]
+    ,[LEFT, FRONT, BOTTOM, BACK, TOP, RIGHT]
+];
+
+fn rotate_centres(centres: [u8; 6], rotation: usize) -> [u8; 6]{
+    let mut result = centres;
+    for (f, centre) in result.iter_mut().enumerate(){
+        *centre = centres[ROTATION_CENTRES[rotation - ROTATE_X][f]];
+    }
+    result
+}
+
+// The 24 ways that the centres of a new cube can be turned, the first is how they start
+pub(crate) fn centre_orientations() -> [[u8; 6]; 24]{
+    let mut orientations = [CubieCube::SOLVED.centres; 24];
+    let (mut found, mut i) = (1, 0);
+    while i < found{
+        for rotation in [ROTATE_X, ROTATE_Y]{
+            let rotated = rotate_centres(orientations[i], rotation);
+            if !orientations[..found].contains(&rotated) {
+                orientations[found] = rotated;
+                found += 1;
+            }
+        }
+        i += 1;
+    }
+    orientations
+}
+
+// Whether the centres are one of the 24 orientations of a new cube
+fn centres_possible(centres: [u8; 6]) -> bool{
+    centre_orientations().contains(&centres)
+}
+
+// (face, subface) of each corner facelet. The U or D facelet is first, the others follow clockwise
+pub(crate) const CORNER_FACELETS: [[(usize, usize); 3]; 8] = [
+    [(TOP, 8), (RIGHT, 0), (FRONT, 2)]
+    ,[(TOP, 6), (FRONT, 0), (LEFT, 2)]
+    ,[(TOP, 0), (LEFT, 0), (BACK, 2)]
+    ,[(TOP, 2), (BACK, 0), (RIGHT, 2)]
+    ,[(BOTTOM, 6), (FRONT, 8), (RIGHT, 6)]
+    ,[(BOTTOM, 8), (LEFT, 8), (FRONT, 6)]
+    ,[(BOTTOM, 2), (BACK, 8), (LEFT, 6)]
+    ,[(BOTTOM, 0), (RIGHT, 8), (BACK, 6)]
+];
+
+// (face, subface) of each edge facelet. The U or D facelet is first, or the F or B facelet for the middle layer
+pub(crate) const EDGE_FACELETS: [[(usize, usize); 2]; 12] = [
+    [(TOP, 5), (RIGHT, 1)]
+    ,[(TOP, 7), (FRONT, 1)]
+    ,[(TOP, 3), (LEFT, 1)]
+    ,[(TOP, 1), (BACK, 1)]
+    ,[(BOTTOM, 3), (RIGHT, 7)]
+    ,[(BOTTOM, 7), (FRONT, 7)]
+    ,[(BOTTOM, 5), (LEFT, 7)]
+    ,[(BOTTOM, 1), (BACK, 7)]
+    ,[(FRONT, 5), (RIGHT, 3)]
+    ,[(FRONT, 3), (LEFT, 5)]
+    ,[(BACK, 5), (LEFT, 3)]
+    ,[(BACK, 3), (RIGHT, 5)]
+];
+
+// cp[i] is the corner that is in position i, co[i] is how far it is twisted clockwise
+// ep[i] is the edge that is in position i, eo[i] is 1 if it is flipped
+// centres[f] is the face that the centre on face f started on
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CubieCube{
+    pub cp: [u8; 8]
+    ,pub co: [u8; 8]
+    ,pub ep: [u8; 12]
+    ,pub eo: [u8; 12]
+    ,pub centres: [u8; 6]
+}
+
+impl CubieCube{
+    pub const SOLVED: CubieCube = CubieCube{
+        cp: [0, 1, 2, 3, 4, 5, 6, 7]
+        ,co: [0; 8]
+        ,ep: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]
+        ,eo: [0; 12]
+        ,centres: [0, 1, 2, 3, 4, 5]
+    };
+
+    // Clockwise quarter turn of one of the six outer faces
+    pub fn face_move(face: usize) -> CubieCube{
+        let (cp, co, ep, eo) = match face {
+            TOP => ([3, 0, 1, 2, 4, 5, 6, 7], [0; 8], [3, 0, 1, 2, 4, 5, 6, 7, 8, 9, 10, 11], [0; 12])
+            ,RIGHT => ([4, 1, 2, 0, 7, 5, 6, 3], [2, 0, 0, 1, 1, 0, 0, 2], [8, 1, 2, 3, 11, 5, 6, 7, 4, 9, 10, 0], [0; 12])
+            ,FRONT => ([1, 5, 2, 3, 0, 4, 6, 7], [1, 2, 0, 0, 2, 1, 0, 0], [0, 9, 2, 3, 4, 8, 6, 7, 1, 5, 10, 11], [0, 1, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0])
+            ,BOTTOM => ([0, 1, 2, 3, 5, 6, 7, 4], [0; 8], [0, 1, 2, 3, 5, 6, 7, 4, 8, 9, 10, 11], [0; 12])
+            ,LEFT => ([0, 2, 6, 3, 4, 1, 5, 7], [0, 1, 2, 0, 0, 2, 1, 0], [0, 1, 10, 3, 4, 5, 9, 7, 8, 2, 6, 11], [0; 12])
+            ,BACK => ([0, 1, 3, 7, 4, 5, 2, 6], [0, 0, 1, 2, 0, 0, 2, 1], [0, 1, 2, 11, 4, 5, 6, 10, 8, 9, 3, 7], [0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 1, 1])
+            ,_ => panic!("Not an outer face: {}", face)
+        };
+        CubieCube{cp, co, ep, eo, centres: CubieCube::SOLVED.centres}
+    }
+
+    // The pieces of a solved cube after a whole cube rotation, as if the centres hadn't moved
+    fn rotation(rotation: usize) -> CubieCube{
+        let (cp, co, ep, eo) = match rotation {
+            ROTATE_X => ([4, 5, 1, 0, 7, 6, 2, 3], [2, 1, 2, 1, 1, 2, 1, 2], [8, 5, 9, 1, 11, 7, 10, 3, 4, 6, 2, 0], [0, 1, 0, 1, 0, 1, 0, 1, 0, 0, 0, 0])
+            ,ROTATE_Y => ([3, 0, 1, 2, 7, 4, 5, 6], [0; 8], [3, 0, 1, 2, 7, 4, 5, 6, 11, 8, 9, 10], [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1])
+            ,ROTATE_Z => ([1, 5, 6, 2, 0, 4, 7, 3], [1, 2, 1, 2, 2, 1, 2, 1], [2, 9, 6, 10, 0, 8, 4, 11, 1, 5, 7, 3], [1; 12])
+            ,_ => panic!("Not a rotation: {}", rotation)
+        };
+        CubieCube{cp, co, ep, eo, centres: CubieCube::SOLVED.centres}
+    }
+
+    // The cube you get by doing self, then other. Only the pieces are combined, the centres stay as they are in self
+    pub fn multiply(&self, other: &CubieCube) -> CubieCube{
+        let mut result = *self;
+        for i in 0..8{
+            let from = other.cp[i] as usize;
+            result.cp[i] = self.cp[from];
+            result.co[i] = (self.co[from] + other.co[i]) % 3;
+        }
+        for i in 0..12{
+            let from = other.ep[i] as usize;
+            result.ep[i] = self.ep[from];
+            result.eo[i] = (self.eo[from] + other.eo[i]) % 2;
+        }
+        result
+    }
+
+    // The pieces that undo self
+    pub fn inverse(&self) -> CubieCube{
+        let mut result = *self;
+        for i in 0..8{
+            let piece = self.cp[i] as usize;
+            result.cp[piece] = i as u8;
+            result.co[piece] = (3 - self.co[i]) % 3;
+        }
+        for i in 0..12{
+            let piece = self.ep[i] as usize;
+            result.ep[piece] = i as u8;
+            result.eo[piece] = self.eo[i];
+        }
+        result
+    }
+
+    // Whole cube rotations move the pieces and the centres together, so the pieces are renumbered
+    fn rotate(&mut self, rotation: usize){
+        let r = CubieCube::rotation(rotation);
+        let centres = rotate_centres(self.centres, rotation);
+        *self = r.inverse().multiply(self).multiply(&r);
+        self.centres = centres;
+    }
+
+    fn quarter_turn(&mut self, face: usize){
+        match face {
+            ROTATE_X | ROTATE_Y | ROTATE_Z => self.rotate(face)
+            // Each slice is the rotation about its axis with the two outer faces turned back
+            ,CENTER_LR => {
+                self.quarter_turn(RIGHT);
+                for _ in 0..3{
+                    self.quarter_turn(LEFT);
+                    self.rotate(ROTATE_X);
+                }
+            }
+            ,CENTER_BT => {
+                self.quarter_turn(TOP);
+                for _ in 0..3{
+                    self.quarter_turn(BOTTOM);
+                    self.rotate(ROTATE_Y);
+                }
+            }
+            ,CENTER_FB => {
+                self.quarter_turn(BACK);
+                for _ in 0..3{
+                    self.quarter_turn(FRONT);
+                }
+                self.rotate(ROTATE_Z);
+            }
+            ,face => *self = self.multiply(&CubieCube::face_move(face))
+        }
+    }
+
+    // One layer counted in from a face, turning the same way as the face
+    fn layer_turn(&mut self, face: usize, depth: usize){
+        let (layer, turns) = match (depth, face) {
+            (0, face) => (face, 1)
+            ,(1, RIGHT) => (CENTER_LR, 3)
+            ,(1, LEFT) => (CENTER_LR, 1)
+            ,(1, TOP) => (CENTER_BT, 3)
+            ,(1, BOTTOM) => (CENTER_BT, 1)
+            ,(1, FRONT) => (CENTER_FB, 1)
+            ,(1, _) => (CENTER_FB, 3)
+            ,(_, face) => (OPPOSITE[face], 3)
+        };
+        for _ in 0..turns{
+            self.quarter_turn(layer);
+        }
+    }
+
+    // Does the same as Cube::twist
+    pub fn twist(&mut self, twist: Twist){
+        let (face, layers) = twist.layers(3);
+        for _ in 0..twist.quarter_turns(){
+            if twist.face < FAKE_FACE_MIN {
+                for depth in layers.clone(){
+                    self.layer_turn(face, depth);
+                }
+            }
+            else {
+                self.quarter_turn(twist.face);
+            }
+        }
+    }
+
+    // Every piece is in place, whichever way round the centres are
+    pub fn is_solved(&self) -> bool{
+        self.cp == CubieCube::SOLVED.cp && self.co == CubieCube::SOLVED.co && self.ep == CubieCube::SOLVED.ep && self.eo == CubieCube::SOLVED.eo
+    }
+
+    // Reads the pieces from the facelet colours, the colours are matched against the centres,
+    // so a cube that has been rotated or had slices turned is read relative to where its centres are now
+    pub fn from_cube(cube: &Cube) -> Result<CubieCube, StateError>{
+        let colors = centres(&Cube::new());
+        let centres = centres(cube);
+        let mut result = CubieCube::SOLVED;
+        for (f, centre) in result.centres.iter_mut().enumerate(){
+            *centre = colors.iter().position(|c| *c == centres[f]).ok_or(StateError::UnknownColour)? as u8;
+        }
+        if !centres_possible(result.centres) {
+            return Err(StateError::ImpossibleCentres);
+        }
+        let face_of = |(f, s): (usize, usize)| -> Result<usize, StateError>{
+            let col = cube.faces[f].subfaces[s].color;
+            centres.iter().position(|c| *c == col).ok_or(StateError::UnknownColour)
+        };
+
+        for (i, facelets) in CORNER_FACELETS.iter().enumerate(){
+            let faces = [face_of(facelets[0])?, face_of(facelets[1])?, face_of(facelets[2])?];
+            let ori = faces.iter().position(|f| *f == TOP || *f == BOTTOM).ok_or(StateError::ImpossibleCorner)?;
+            let col1 = faces[(ori + 1) % 3];
+            let col2 = faces[(ori + 2) % 3];
+            let piece = CORNER_FACELETS.iter().position(|c| c[0].0 == faces[ori] && c[1].0 == col1 && c[2].0 == col2).ok_or(StateError::ImpossibleCorner)?;
+            result.cp[i] = piece as u8;
+            result.co[i] = ori as u8;
+        }
+        for (i, facelets) in EDGE_FACELETS.iter().enumerate(){
+            let faces = [face_of(facelets[0])?, face_of(facelets[1])?];
+            let (piece, ori) = EDGE_FACELETS.iter().enumerate().find_map(|(j, e)|{
+                if e[0].0 == faces[0] && e[1].0 == faces[1] { Some((j, 0)) }
+                else if e[0].0 == faces[1] && e[1].0 == faces[0] { Some((j, 1)) }
+                else { None }
+            }).ok_or(StateError::ImpossibleEdge)?;
+            result.ep[i] = piece as u8;
+            result.eo[i] = ori;
+        }
+        Ok(result)
+    }
+
+    // The facelet view of this
+    pub fn to_cube(self) -> Cube{
+        let mut cube = Cube::new();
+        let colors = centres(&cube);
+        let centre = |f: usize| colors[self.centres[f] as usize];
+        let mut colors = [[centre(0); 9]; 6];
+        for (f, face) in colors.iter_mut().enumerate(){
+            *face = [centre(f); 9];
+        }
+        for (i, facelets) in CORNER_FACELETS.iter().enumerate(){
+            let piece = &CORNER_FACELETS[self.cp[i] as usize];
+            for k in 0..3{
+                let (f, s) = facelets[(k + self.co[i] as usize) % 3];
+                colors[f][s] = centre(piece[k].0);
+            }
+        }
+        for (i, facelets) in EDGE_FACELETS.iter().enumerate(){
+            let piece = &EDGE_FACELETS[self.ep[i] as usize];
+            for k in 0..2{
+                let (f, s) = facelets[(k + self.eo[i] as usize) % 2];
+                colors[f][s] = centre(piece[k].0);
+            }
+        }
+        for (face, colors) in cube.faces.iter_mut().zip(colors){
+            for (subface, color) in face.subfaces.iter_mut().zip(colors){
+                subface.color = color;
+            }
+        }
+        cube
+    }
+
+    // Reads the pieces like from_cube, after checking everything that could stop the cube being
+    // reached by twisting a solved cube
+    pub fn validated(cube: &Cube) -> Result<CubieCube, StateError>{
+        let mut counts = [0; 6];
+        for face in &cube.faces[0..6]{
+            for subface in &face.subfaces{
+                let c = COLORS.iter().position(|c| *c == subface.color).ok_or(StateError::UnknownColour)?;
+                counts[c] += 1;
+            }
+        }
+        if let Some(c) = counts.iter().position(|n| *n != 9) {
+            return Err(StateError::ColourCount(COLORS[c], counts[c]));
+        }
+        let cubies = CubieCube::from_cube(cube)?;
+        cubies.verify()?;
+        Ok(cubies)
+    }
+
+    // Checks that this could be reached by twisting a solved cube
+    pub fn verify(&self) -> Result<(), StateError>{
+        for p in 0..8{
+            if !self.cp.contains(&p){
+                return Err(StateError::DuplicateCorner);
+            }
+        }
+        for p in 0..12{
+            if !self.ep.contains(&p){
+                return Err(StateError::DuplicateEdge);
+            }
+        }
+        if self.co.iter().map(|&o| o as u32).sum::<u32>() % 3 != 0{
+            return Err(StateError::TwistedCorner);
+        }
+        if self.eo.iter().map(|&o| o as u32).sum::<u32>() % 2 != 0{
+            return Err(StateError::FlippedEdge);
+        }
+        if parity(&self.cp) != parity(&self.ep){
+            return Err(StateError::Parity);
+        }
+        Ok(())
+    }
+}
+
+// Parity of a permutation, true if it needs an odd number of swaps
+pub(crate) fn parity(perm: &[u8]) -> bool{
+    let mut odd = false;
+    for i in 0..perm.len(){
+        for j in i+1..perm.len(){
+            if perm[i] > perm[j]{
+                odd = !odd;
+            }
+        }
+    }
+    odd
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Cube, Colors, Twist, TOP, FRONT, LEFT, BACK, RIGHT, BOTTOM};
+    use super::{CubieCube, StateError};
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn face_moves_match_twists() {
+        for face in [TOP, FRONT, LEFT, BACK, RIGHT, BOTTOM]{
+            let mut cube = Cube::new();
+            cube.twist(Twist{face, reverse: false, double: false, wide: false, layer: 0});
+            let cubies = CubieCube::from_cube(&cube).unwrap();
+            assert_eq!(cubies, CubieCube::SOLVED.multiply(&CubieCube::face_move(face)));
+            assert!(cubies.verify().is_ok());
+        }
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn read_scrambled() {
+        let mut cube = Cube::new();
+        let scramble = "R U2 F' L D B2 R' U F2 D' L2 B";
+        cube.twists(scramble).unwrap();
+        let mut cubies = CubieCube::SOLVED;
+        for t in Twist::seq_from_string(scramble).unwrap(){
+            for _ in 0..t.quarter_turns(){
+                cubies = cubies.multiply(&CubieCube::face_move(t.face));
+            }
+        }
+        assert_eq!(CubieCube::from_cube(&cube).unwrap(), cubies);
+        assert_eq!(cubies.to_cube().serialise(), cube.serialise());
+        // Slices move the centres, the pieces are read relative to them
+        let mut cube = Cube::new();
+        cube.twists("M E S").unwrap();
+        assert!(CubieCube::from_cube(&cube).unwrap().verify().is_ok());
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn twists_match_cube() {
+        let sequences = [
+            "x y z x' y' z' x2 y2 z2"
+            ,"M E S M' E' S' M2 E2 S2"
+            ,"Rw Lw Uw Dw Fw Bw Rw' Lw' Uw' Dw' Fw' Bw' Rw2 Lw2 Uw2 Dw2 Fw2 Bw2"
+            ,"R U M' x2 E S' y F2 z Dw' B Lw2 E2 x' L' S2 Fw"
+            ,"2R 2U' 3F 2D2 3Rw 3Lw' 3Uw2 5B 4Dw"
+        ];
+        for sequence in sequences{
+            let mut cube = Cube::new();
+            let mut cubies = CubieCube::SOLVED;
+            for t in Twist::seq_from_string(sequence).unwrap(){
+                cube.twist(t);
+                cubies.twist(t);
+                assert_eq!(CubieCube::from_cube(&cube), Ok(cubies), "after {} in {}", t, sequence);
+                assert_eq!(cubies.to_cube().serialise(), cube.serialise());
+            }
+        }
+        let mut cubies = CubieCube::SOLVED;
+        cubies.twist(Twist::from_string("M").unwrap());
+        assert!(!cubies.is_solved());
+        cubies.twist(Twist::from_string("R'").unwrap());
+        cubies.twist(Twist::from_string("L").unwrap());
+        assert!(cubies.is_solved());
+        assert_ne!(cubies, CubieCube::SOLVED);
+        let mut c = CubieCube::SOLVED;
+        c.twist(Twist::from_string("R").unwrap());
+        c.twist(Twist::from_string("U").unwrap());
+        assert!(c.multiply(&c.inverse()).is_solved());
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn bad_states() {
+        let mut cube = Cube::new();
+        cube.deserialise("WWWWWWWWWRRRRRRRRRGGGGGGGGGOOOOOOOOOBBBBBBBBBYYYYYYYYR").unwrap();
+        assert!(CubieCube::from_cube(&cube).is_err());
+        // A single twisted corner
+        let mut cube = Cube::new();
+        cube.deserialise("WWWWWWWWBRRWRRRRRRGGGGGGGGGOOOOOOOOORBBBBBBBBYYYYYYYYY").unwrap();
+        assert_eq!(CubieCube::from_cube(&cube).unwrap().verify(), Err(StateError::TwistedCorner));
+    }
+
+    fn validate(state: &str) -> Result<CubieCube, StateError>{
+        let mut cube = Cube::new();
+        cube.deserialise(state).unwrap();
+        CubieCube::validated(&cube)
+    }
+
+    fn validate_cubies(c: CubieCube) -> Result<CubieCube, StateError>{
+        CubieCube::validated(&c.to_cube())
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn validation() {
+        assert_eq!(validate("WWWWWWWWWRRRRRRRRRGGGGGGGGGOOOOOOOOOBBBBBBBBBYYYYYYYYY"), Ok(CubieCube::SOLVED));
+        assert_eq!(validate("WWWWWWWWWRRRRRRRRRGGGGGGGGGOOOOOOOOOBBBBBBBBBYYYYYYYY "), Err(StateError::UnknownColour));
+        assert_eq!(validate("WWWWWWWWWRRRRRRRRRGGGGGGGGGOOOOOOOOOBBBBBBBBBYYYYYYYYR"), Err(StateError::ColourCount(Colors::Red, 10)));
+        // Front and back swapped, it looks solved but the colours are mirrored
+        assert_eq!(validate("WWWWWWWWWOOOOOOOOOGGGGGGGGGRRRRRRRRRBBBBBBBBBYYYYYYYYY"), Err(StateError::ImpossibleCentres));
+        // Two stickers swapped on one corner
+        assert_eq!(validate("WWWWWWWWWRRBRRRRRRGGGGGGGGGOOOOOOOOORBBBBBBBBYYYYYYYYY"), Err(StateError::ImpossibleCorner));
+        // Top front and bottom front edges have their front stickers swapped
+        assert_eq!(validate("WWWWWWWWWRYRRRRRRRGGGGGGGGGOOOOOOOOOBBBBBBBBBYYYYYYYRY"), Err(StateError::ImpossibleEdge));
+
+        let mut c = CubieCube::SOLVED;
+        c.cp[1] = 0;
+        c.ep[8] = 9;
+        assert_eq!(validate_cubies(c), Err(StateError::DuplicateCorner));
+        let mut c = CubieCube::SOLVED;
+        c.ep[8] = 9;
+        c.ep[10] = 11;
+        assert_eq!(validate_cubies(c), Err(StateError::DuplicateEdge));
+        let mut c = CubieCube::SOLVED;
+        c.co[0] = 1;
+        assert_eq!(validate_cubies(c), Err(StateError::TwistedCorner));
+        let mut c = CubieCube::SOLVED;
+        c.eo[0] = 1;
+        assert_eq!(validate_cubies(c), Err(StateError::FlippedEdge));
+        let mut c = CubieCube::SOLVED;
+        c.ep.swap(0, 1);
+        assert_eq!(validate_cubies(c), Err(StateError::Parity));
+
+        // Anything reachable by twisting is fine, whichever way round the centres have ended up
+        let mut cube = Cube::new();
+        cube.twists("R U M' x2 E S' y F2 z").unwrap();
+        assert!(CubieCube::validated(&cube).is_ok());
+        assert_eq!(StateError::ColourCount(Colors::Red, 10).to_string(), "There are 10 Red subfaces, there should be 9");
+        assert_eq!(StateError::Parity.code(), "parity");
+    }
+}
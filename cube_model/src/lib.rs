@@ -1,27 +1,59 @@
 #![cfg_attr(feature="without_std", no_std)]
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+use core::ops::Range;
+use core::hash::{Hash, Hasher};
+
+pub mod cubie;
+pub use cubie::StateError;
+pub mod packed;
+pub mod catalogue;
+#[cfg(not(feature="without_std"))]
+pub mod solver;
+#[cfg(not(feature="without_std"))]
+pub mod beginner;
+#[cfg(not(feature="without_std"))]
+pub mod scramble;
+#[cfg(not(feature="without_std"))]
+pub mod sequence;
+#[cfg(not(feature="without_std"))]
+pub mod cfop;
+#[cfg(not(feature="without_std"))]
+pub mod symmetry;
+#[cfg(all(feature="serde", not(feature="without_std")))]
+mod serde_support;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Colors{
     White, Red, Blue, Green, Yellow, Orange, Blank
 }
 
-#[derive(Clone, Copy, Debug)]
+// Rotation is how many quarter turns clockwise the subface has turned since the cube was made, it doesn't
+// matter on a normal cube, but renderers can use it to show which way round the stickers are on a supercube
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct SubFace{
     pub color: Colors
-    ,next_color: Colors
+    ,pub rotation: u8
 }
 
-#[derive(Clone, Copy, Debug)]
-pub struct Face{
-    pub subfaces: [SubFace; 9]
-    ,adjacent: [(usize, isize, isize); 4]
+// The subfaces are in rows, starting at the top left. S is always N*N, it has to be given separately
+// because array lengths can't be worked out from generic parameters
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Face<const N: usize = 3, const S: usize = 9>{
+    pub subfaces: [SubFace; S]
 }
 
+// A cube with N subfaces along each edge, on its own Cube is the 3x3 cube
+// On a supercube the stickers have to be the right way round as well, which is only noticeable on the centres
 #[derive(Clone, Copy, Debug)]
-pub struct Cube{
-    pub faces: [Face; 9]
+pub struct Cube<const N: usize = 3, const S: usize = 9>{
+    pub faces: [Face<N, S>; 6]
+    ,pub supercube: bool
 }
 
+pub type Cube2 = Cube<2, 4>;
+pub type Cube4 = Cube<4, 16>;
+pub type Cube5 = Cube<5, 25>;
+
 impl Colors{
     pub fn shortname(&self) -> &'static str{
         match self{
@@ -48,90 +80,51 @@ impl Colors{
     }
 }
 
-impl Face{
-    fn new(color: Colors, adjacent: [(usize, isize, isize);4]) -> Face {
+impl<const N: usize, const S: usize> Face<N, S>{
+    fn new(color: Colors) -> Face<N, S> {
         Face{
-            subfaces: [
-                SubFace{color: color, next_color: color};9
-            ]
-            ,adjacent: adjacent
+            subfaces: [SubFace{color, rotation: 0}; S]
         }
     }
 
-    fn copy_from(&mut self, other: [SubFace;9], doffset: isize, dstep: isize, soffset: isize, sstep: isize) -> [[SubFace;9];2] {
-        let window: [SubFace;6] = [
-            self.subfaces[doffset as usize]
-            ,self.subfaces[(doffset+dstep) as usize]
-            ,self.subfaces[(doffset+dstep+dstep) as usize]
-            ,other[soffset as usize]
-            ,other[(soffset+sstep) as usize]
-            ,other[(soffset+sstep+sstep) as usize]
-        ];
-
-        let mut intermediates = [self.subfaces;2];
+    // Position k of the ring of subfaces that is `depth` in from the edge of the face, going clockwise from its top left
+    fn ring(depth: usize, k: usize) -> usize{
+        let side = N - 1 - 2 * depth;
+        let (far, j) = (N - 1 - depth, k % side);
+        let (row, col) = match k / side {
+            0 => (depth, depth + j)
+            ,1 => (depth + j, far)
+            ,2 => (far, far - j)
+            ,_ => (far - j, depth)
+        };
+        row * N + col
+    }
 
-        for f in 0..2{
-            for i in 0..3{
-                let d = (doffset + (i * dstep)) as usize;
-                let c = window[f + i as usize + 1].color;
-                intermediates[f][d].color = c;
-                intermediates[f][d].next_color = c;
+    // The subfaces part of the way through a quarter turn, given as a fraction. The rings that don't have subfaces
+    // in the right places get as close as they can. The stickers only count as turned after the whole quarter turn
+    fn turned(&self, reverse: bool, turned: (usize, usize)) -> [SubFace; S]{
+        let mut subfaces = self.subfaces;
+        for depth in 0..N/2{
+            let side = N - 1 - 2 * depth;
+            let len = side * 4;
+            let shift = side * turned.0 / turned.1;
+            for k in 0..len{
+                let from = if reverse {(k + shift) % len} else {(k + len - shift) % len};
+                subfaces[Face::<N, S>::ring(depth, k)] = self.subfaces[Face::<N, S>::ring(depth, from)];
             }
         }
-
-        for i in 0..3{
-            let d = (doffset + (i * dstep)) as usize;
-            self.subfaces[d].next_color = window[3+i as usize].color;
+        if turned.0 == turned.1 {
+            for s in &mut subfaces{
+                s.rotation = (s.rotation + if reverse {3} else {1}) % 4;
+            }
         }
-
-        intermediates
-    }
-
-    fn update(&mut self){
-        for s in &mut self.subfaces{
-            s.color = s.next_color;
-        }
-    }
-
-    fn twist(&mut self, reverse: bool) -> [SubFace;9]{
-        let f = &self.subfaces;
-        let intermediate = if !reverse {[
-            f[3], f[0], f[1]
-            ,f[6], f[4], f[2]
-            ,f[7], f[8], f[5]
-        ]}
-        else {[
-            f[1], f[2], f[5]
-            ,f[0], f[4], f[8]
-            ,f[3], f[6], f[7]
-        ]};
-        self.subfaces = if !reverse {[
-            f[6],f[3],f[0]
-            ,f[7],f[4],f[1]
-            ,f[8],f[5],f[2]
-        ]}
-        else {[
-            f[2],f[5],f[8]
-            ,f[1],f[4],f[7]
-            ,f[0],f[3],f[6]
-        ]};
-        intermediate
+        subfaces
     }
 
     #[cfg(not(feature="without_std"))]
     pub fn simple_string(&self) -> String{
-        let f = &self.subfaces;
-        format!("{}{}{}\n{}{}{}\n{}{}{}"
-            ,f[0].color.shortname()
-            ,f[1].color.shortname()
-            ,f[2].color.shortname()
-            ,f[3].color.shortname()
-            ,f[4].color.shortname()
-            ,f[5].color.shortname()
-            ,f[6].color.shortname()
-            ,f[7].color.shortname()
-            ,f[8].color.shortname()
-        )
+        let rows: Vec<String> = self.subfaces.chunks(N).map(|row| row.iter().map(|s| s.color.shortname()).collect()).collect();
+        rows.join("\n")
     }
 }
 
@@ -146,55 +139,202 @@ pub const FAKE_FACE_MIN: usize = 6;
 pub const CENTER_FB: usize = 6;
 pub const CENTER_LR: usize = 7;
 pub const CENTER_BT: usize = 8;
+// Whole-cube rotations, these are not faces at all, they turn every layer on one axis
+pub const ROTATION_MIN: usize = 9;
+pub const ROTATE_X: usize = 9;
+pub const ROTATE_Y: usize = 10;
+pub const ROTATE_Z: usize = 11;
 
-// Represents any turn that is a single turn according to Quarter Slice Turn Metric
-// (This means that it can only represent 90-degree turns, and not 180-degree turns
-// and that it also does not represent full-cube rotations)
-#[derive(Copy, Clone, Debug, PartialEq)]
+// Represents any turn that is a single turn according to Half Turn Metric,
+// a slice turn, or a full-cube rotation (x, y or z)
+// A half turn is one twist with `double` set, the direction of a half turn only matters for animation
+// A wide twist turns a face together with the slice next to it (Rw = R M'), only faces can be wide
+// On bigger cubes, layer picks a layer counting in from the face, 2R is layer 2, and a wide twist turns every layer
+// up to that one, 3Rw is layers 1 to 3. Layer 0 is the face on its own, or two layers for a wide twist
+// Slices turn every layer between the two faces, which is the one middle layer on a 3x3
+#[derive(Copy, Clone, Debug)]
 pub struct Twist{
     pub face: usize
     ,pub reverse: bool
+    ,pub double: bool
+    ,pub wide: bool
+    ,pub layer: usize
+}
+
+// R2 and R2' are the same twist, they only animate differently
+impl PartialEq for Twist{
+    fn eq(&self, other: &Twist) -> bool {
+        self.face == other.face
+            && self.double == other.double
+            && (self.double || self.reverse == other.reverse)
+            && self.wide == other.wide
+            && self.layer == other.layer
+    }
+}
+
+// How to read lowercase face letters when parsing twists
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Notation{
+    // Lowercase face letters are wide twists (r = Rw)
+    Standard
+    // Lowercase face letters are plain face twists (r = R), this is what the device sends
+    ,Legacy
+}
+
+// Ways of counting the length of a sequence of twists
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Metric{
+    // Quarter Turn Metric: a half turn is two moves, a slice turn is two face turns
+    QTM
+    // Half Turn Metric: any face turn is one move, a slice turn is two face turns
+    ,HTM
+    // Quarter Slice Turn Metric: like QTM, but a slice quarter turn is one move
+    ,QSTM
+    // Slice Turn Metric: like HTM, but a slice turn is one move
+    ,STM
 }
 
 impl Twist{
 
     pub fn from_bytes(s: &[u8]) -> Result<Twist, &'static str>{
-        let l = s.len();
-        if l < 1 || l > 3 {
-            Err("Invalid twist string")
-        }
-        else{
-            let reverse = s[s.len()-1] == b'\'';
-            let face = match s[0] {
-                b'T'|b't' => Ok(TOP),
-                b'U'|b'u' => Ok(TOP),
-                b'F'|b'f' => Ok(FRONT),
-                b'L'|b'l' => Ok(LEFT),
-                b'B'|b'b' => match (l, reverse) {
-                    (1, false) | (2, true) => {Ok(BACK)},
-                    (2, false) | (3, true) => {
-                        match s[1] {
-                            b'A' => {Ok(BACK)},
-                            b'a' => {Ok(BACK)},
-                            b' ' => {Ok(BACK)},
-                            b'O' => {Ok(BOTTOM)},
-                            b'o' => {Ok(BOTTOM)},
-                            _ => {Err("Invalid twist string")}
-                        }
-                    },
-                    _=>{Err("Invalid twist string")}
-                },
-                b'R'|b'r' => Ok(RIGHT),
-                b'D'|b'd' => Ok(BOTTOM),
-                b'S'|b's' => Ok(CENTER_FB),
-                b'M'|b'm' => Ok(CENTER_LR),
-                b'E'|b'e' => Ok(CENTER_BT),
-                _=> Err("Invalid twist string"),
-            }?;
-            Ok(Twist{
-                face: face
-                ,reverse: reverse
-            })
+        Twist::from_bytes_in(s, Notation::Standard)
+    }
+
+    pub fn from_bytes_in(s: &[u8], notation: Notation) -> Result<Twist, &'static str>{
+        // Big cube layers come before the face
+        let digits = s.iter().take_while(|c| c.is_ascii_digit()).count();
+        if digits > 3 {
+            return Err("Invalid twist string");
+        }
+        let layer = s[..digits].iter().fold(0, |n, c| n * 10 + (c - b'0') as usize);
+        let s = &s[digits..];
+        if s.is_empty() || (digits > 0 && layer == 0) {
+            return Err("Invalid twist string");
+        }
+        let (face, modifiers) = match s[0] {
+            b'T'|b't' => (TOP, &s[1..]),
+            b'U'|b'u' => (TOP, &s[1..]),
+            b'F'|b'f' => (FRONT, &s[1..]),
+            b'L'|b'l' => (LEFT, &s[1..]),
+            // Back and bottom can also be written as BA and BO
+            b'B'|b'b' => match s.get(1) {
+                Some(b'A'|b'a') => (BACK, &s[2..]),
+                Some(b'O'|b'o') => (BOTTOM, &s[2..]),
+                _ => (BACK, &s[1..]),
+            },
+            b'R'|b'r' => (RIGHT, &s[1..]),
+            b'D'|b'd' => (BOTTOM, &s[1..]),
+            b'S'|b's' => (CENTER_FB, &s[1..]),
+            b'M'|b'm' => (CENTER_LR, &s[1..]),
+            b'E'|b'e' => (CENTER_BT, &s[1..]),
+            b'X'|b'x' => (ROTATE_X, &s[1..]),
+            b'Y'|b'y' => (ROTATE_Y, &s[1..]),
+            b'Z'|b'z' => (ROTATE_Z, &s[1..]),
+            _=> return Err("Invalid twist string"),
+        };
+        // BA and BO are names, not wide twists, even when written in lowercase
+        let lowercase_face = face < FAKE_FACE_MIN && s[0].is_ascii_lowercase() && modifiers.len() == s.len() - 1;
+        let (w, modifiers) = match modifiers.split_first() {
+            Some((b'w', rest)) => (true, rest),
+            _ => (false, modifiers),
+        };
+        let wide = match (notation, lowercase_face, w) {
+            (_, _, true) if face >= FAKE_FACE_MIN => return Err("Only face turns can be wide"),
+            (Notation::Standard, true, true) => return Err("Invalid twist string"),
+            (Notation::Standard, true, false) => true,
+            (_, _, w) => w,
+        };
+        if digits > 0 && face >= FAKE_FACE_MIN {
+            return Err("Only face turns can have a layer");
+        }
+        // 1R is just R, and 2Rw is just Rw
+        let layer = match (wide, layer) {
+            (false, 1) | (true, 2) => 0
+            ,(_, layer) => layer
+        };
+        // The device pads single letter twists with a space
+        let (double, reverse) = match modifiers {
+            b"" | b" " => (false, false),
+            b"'" => (false, true),
+            b"2" => (true, false),
+            b"2'" | b"'2" => (true, true),
+            _ => return Err("Invalid twist string"),
+        };
+        Ok(Twist{
+            face: face
+            ,reverse: reverse
+            ,double: double
+            ,wide: wide
+            ,layer: layer
+        })
+    }
+
+    // Number of moves this twist counts as in the given metric, rotations are free in all metrics
+    pub fn cost(&self, metric: Metric) -> usize{
+        let turns = if self.double {2} else {1};
+        if self.face >= ROTATION_MIN {
+            0
+        }
+        else if self.face >= FAKE_FACE_MIN {
+            match metric {
+                Metric::QTM => 2 * turns
+                ,Metric::HTM => 2
+                ,Metric::QSTM => turns
+                ,Metric::STM => 1
+            }
+        }
+        else {
+            match metric {
+                Metric::QTM | Metric::QSTM => turns
+                ,Metric::HTM | Metric::STM => 1
+            }
+        }
+    }
+
+    // The twist that undoes this one, a half turn undoes itself but animates back the way it came
+    pub fn inverse(&self) -> Twist{
+        Twist{reverse: !self.reverse, ..*self}
+    }
+
+    // Number of clockwise quarter turns that this is the same as
+    pub fn quarter_turns(&self) -> usize{
+        if self.double {2} else if self.reverse {3} else {1}
+    }
+
+    // The shortest twist of the face that is the same as this many clockwise quarter turns, None if it's no twist at all
+    pub fn from_quarter_turns(face: usize, turns: usize) -> Option<Twist>{
+        match turns % 4 {
+            0 => None
+            ,turns => Some(Twist{face, reverse: turns == 3, double: turns == 2, wide: false, layer: 0})
+        }
+    }
+
+    // The face that the layers this turns are counted in from, and which of those layers turn, on a cube with
+    // `size` layers. The face is layer 0, and every layer turns the same way as the face
+    pub fn layers(&self, size: usize) -> (usize, Range<usize>){
+        match self.face {
+            // x follows R, y follows U, z follows F
+            ROTATE_X => (RIGHT, 0..size)
+            ,ROTATE_Y => (TOP, 0..size)
+            ,ROTATE_Z => (FRONT, 0..size)
+            // M follows L, E follows D and S follows F
+            ,CENTER_LR => (LEFT, 1..size - 1)
+            ,CENTER_BT => (BOTTOM, 1..size - 1)
+            ,CENTER_FB => (FRONT, 1..size - 1)
+            ,face if self.wide => (face, 0..self.layer.max(2).min(size))
+            ,face => {
+                let layer = self.layer.max(1).min(size);
+                (face, layer - 1..layer)
+            }
+        }
+    }
+
+    // Faces, slices and rotations on the same axis can be done in either order
+    pub fn axis(&self) -> usize{
+        match self.face {
+            LEFT | RIGHT | CENTER_LR | ROTATE_X => ROTATE_X
+            ,TOP | BOTTOM | CENTER_BT | ROTATE_Y => ROTATE_Y
+            ,_ => ROTATE_Z
         }
     }
 
@@ -203,40 +343,56 @@ impl Twist{
         Twist::from_bytes(s)
     }
 
+    pub fn from_string_in(s: &str, notation: Notation) -> Result<Twist, &'static str>{
+        Twist::from_bytes_in(s.as_bytes(), notation)
+    }
+
+    // Twists separated by spaces, also understands commutators, conjugates and repeated groups, see sequence::parse
     #[cfg(not(feature="without_std"))]
     pub fn seq_from_string(s: &str) -> Result<Vec<Twist>, &'static str>{
-        let mut seq = Vec::new();
-        for m in s.split_whitespace(){
-            let l = m.len();
-            if l < 1{
-                // wut?
-            }
-            else{
-                let b = m.as_bytes();
-                let last = b[b.len()-1];
-                if last == b'2'{
-                    let t = Twist::from_string(&m[0..l-1])?;
-                    seq.push(t.clone());
-                    seq.push(t);
-                }
-                else{
-                    seq.push(Twist::from_string(m)?);
-                }
-            }
-        }
-        Ok(seq)
+        sequence::parse(s)
+    }
+
+    // Twists separated by whitespace, read one at a time without allocating so this works without std.
+    // Brackets aren't understood, only seq_from_string can expand those
+    pub fn iter_from_string(s: &str) -> TwistIter<'_>{
+        Twist::iter_from_string_in(s, Notation::Standard)
+    }
+
+    pub fn iter_from_string_in(s: &str, notation: Notation) -> TwistIter<'_>{
+        TwistIter{s: s.as_bytes(), notation}
     }
 
 }
 
+pub struct TwistIter<'a>{
+    s: &'a [u8]
+    ,notation: Notation
+}
+
+impl Iterator for TwistIter<'_>{
+    type Item = Result<Twist, &'static str>;
+
+    fn next(&mut self) -> Option<Result<Twist, &'static str>> {
+        let start = self.s.iter().position(|c| !c.is_ascii_whitespace())?;
+        let s = &self.s[start..];
+        let len = s.iter().position(|c| c.is_ascii_whitespace()).unwrap_or(s.len());
+        self.s = &s[len..];
+        Some(Twist::from_bytes_in(&s[..len], self.notation))
+    }
+}
+
 #[cfg(not(feature="without_std"))]
 use std::fmt;
 #[cfg(not(feature="without_std"))]
 impl fmt::Display for Twist{
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.layer > 0 {
+            write!(f, "{}", self.layer)?;
+        }
         write!(
             f
-            ,"{}{}"
+            ,"{}{}{}{}"
             ,match self.face {
                 TOP => "U"
                 ,FRONT => "F"
@@ -247,68 +403,123 @@ impl fmt::Display for Twist{
                 ,CENTER_FB => "S"
                 ,CENTER_LR => "M"
                 ,CENTER_BT => "E"
+                ,ROTATE_X => "x"
+                ,ROTATE_Y => "y"
+                ,ROTATE_Z => "z"
                 ,_=>"?"
             }
+            ,if self.wide {"w"} else {""}
+            ,if self.double {"2"} else {""}
             ,if self.reverse {"'"} else {""}
         )
     }
 }
 
+pub fn count_moves(seq: &[Twist], metric: Metric) -> usize{
+    seq.iter().map(|t|t.cost(metric)).sum()
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct Output{
     pub face: usize
     ,pub subface: usize
 }
 
-pub const ALL_TWISTS: [Twist; 18] = [
-    Twist{face:BOTTOM, reverse:false}
-    ,Twist{face:CENTER_BT, reverse:false}
-    ,Twist{face:TOP, reverse:false}
-    ,Twist{face:LEFT, reverse:false}
-    ,Twist{face:CENTER_LR, reverse:false}
-    ,Twist{face:RIGHT, reverse:false}
-    ,Twist{face:FRONT, reverse:false}
-    ,Twist{face:CENTER_FB, reverse:false}
-    ,Twist{face:BACK, reverse:false}
-    ,Twist{face:BOTTOM, reverse:true}
-    ,Twist{face:CENTER_BT, reverse:true}
-    ,Twist{face:TOP, reverse:true}
-    ,Twist{face:LEFT, reverse:true}
-    ,Twist{face:CENTER_LR, reverse:true}
-    ,Twist{face:RIGHT, reverse:true}
-    ,Twist{face:FRONT, reverse:true}
-    ,Twist{face:CENTER_FB, reverse:true}
-    ,Twist{face:BACK, reverse:true}
+pub const ALL_TWISTS: [Twist; 24] = [
+    Twist{face:BOTTOM, reverse:false, double:false, wide:false, layer: 0}
+    ,Twist{face:CENTER_BT, reverse:false, double:false, wide:false, layer: 0}
+    ,Twist{face:TOP, reverse:false, double:false, wide:false, layer: 0}
+    ,Twist{face:LEFT, reverse:false, double:false, wide:false, layer: 0}
+    ,Twist{face:CENTER_LR, reverse:false, double:false, wide:false, layer: 0}
+    ,Twist{face:RIGHT, reverse:false, double:false, wide:false, layer: 0}
+    ,Twist{face:FRONT, reverse:false, double:false, wide:false, layer: 0}
+    ,Twist{face:CENTER_FB, reverse:false, double:false, wide:false, layer: 0}
+    ,Twist{face:BACK, reverse:false, double:false, wide:false, layer: 0}
+    ,Twist{face:BOTTOM, reverse:true, double:false, wide:false, layer: 0}
+    ,Twist{face:CENTER_BT, reverse:true, double:false, wide:false, layer: 0}
+    ,Twist{face:TOP, reverse:true, double:false, wide:false, layer: 0}
+    ,Twist{face:LEFT, reverse:true, double:false, wide:false, layer: 0}
+    ,Twist{face:CENTER_LR, reverse:true, double:false, wide:false, layer: 0}
+    ,Twist{face:RIGHT, reverse:true, double:false, wide:false, layer: 0}
+    ,Twist{face:FRONT, reverse:true, double:false, wide:false, layer: 0}
+    ,Twist{face:CENTER_FB, reverse:true, double:false, wide:false, layer: 0}
+    ,Twist{face:BACK, reverse:true, double:false, wide:false, layer: 0}
+    ,Twist{face:ROTATE_X, reverse:false, double:false, wide:false, layer: 0}
+    ,Twist{face:ROTATE_Y, reverse:false, double:false, wide:false, layer: 0}
+    ,Twist{face:ROTATE_Z, reverse:false, double:false, wide:false, layer: 0}
+    ,Twist{face:ROTATE_X, reverse:true, double:false, wide:false, layer: 0}
+    ,Twist{face:ROTATE_Y, reverse:true, double:false, wide:false, layer: 0}
+    ,Twist{face:ROTATE_Z, reverse:true, double:false, wide:false, layer: 0}
 ];
 
-impl Cube{
-    pub fn new() -> Cube{
+// Which line of subfaces on a neighbouring face a layer passes over, in the order that they line up
+// between neighbouring faces. Layers further in from the turned face use the lines further in from this edge
+#[derive(Clone, Copy)]
+enum Edge{
+    TopRow
+    ,BottomRowReversed
+    ,LeftColumnUp
+    ,RightColumnDown
+}
+
+impl Edge{
+    // Which way along the face the line goes, in quarter turns clockwise from pointing right
+    fn direction(&self) -> u8{
+        match self {
+            Edge::TopRow => 0
+            ,Edge::RightColumnDown => 1
+            ,Edge::BottomRowReversed => 2
+            ,Edge::LeftColumnUp => 3
+        }
+    }
+}
+
+// The four faces around each face, in the order that a clockwise twist moves subfaces backwards through them
+const ADJACENT: [[(usize, Edge); 4]; 6] = [
+    [(BACK, Edge::TopRow), (RIGHT, Edge::TopRow), (FRONT, Edge::TopRow), (LEFT, Edge::TopRow)]
+    ,[(TOP, Edge::BottomRowReversed), (RIGHT, Edge::LeftColumnUp), (BOTTOM, Edge::BottomRowReversed), (LEFT, Edge::RightColumnDown)]
+    ,[(TOP, Edge::LeftColumnUp), (FRONT, Edge::LeftColumnUp), (BOTTOM, Edge::RightColumnDown), (BACK, Edge::RightColumnDown)]
+    ,[(TOP, Edge::TopRow), (LEFT, Edge::LeftColumnUp), (BOTTOM, Edge::TopRow), (RIGHT, Edge::RightColumnDown)]
+    ,[(TOP, Edge::RightColumnDown), (BACK, Edge::LeftColumnUp), (BOTTOM, Edge::LeftColumnUp), (FRONT, Edge::RightColumnDown)]
+    ,[(FRONT, Edge::BottomRowReversed), (RIGHT, Edge::BottomRowReversed), (BACK, Edge::BottomRowReversed), (LEFT, Edge::BottomRowReversed)]
+];
+
+pub(crate) const OPPOSITE: [usize; 6] = [BOTTOM, BACK, RIGHT, FRONT, LEFT, TOP];
+
+// The face order used by other cube software, and the letters for the faces in that order
+const URFDLB: [usize; 6] = [TOP, RIGHT, FRONT, BOTTOM, LEFT, BACK];
+const URFDLB_NAMES: [char; 6] = ['U', 'R', 'F', 'D', 'L', 'B'];
+
+impl<const N: usize, const S: usize> Cube<N, S>{
+    pub fn solved() -> Cube<N, S>{
+        const { assert!(N >= 2 && S == N * N, "A cube needs at least two subfaces along each edge, and S must be N*N") };
         // top front left back right bottom
-        let faces = [
-            Face::new(Colors::White, [(BACK, 0,1), (RIGHT, 0,1), (FRONT, 0,1), (LEFT, 0,1)])
-            ,Face::new(Colors::Red, [(TOP, 8,-1), (RIGHT, 6,-3), (BOTTOM, 8,-1), (LEFT, 2,3)])
-            ,Face::new(Colors::Green, [(TOP, 6,-3), (FRONT, 6,-3), (BOTTOM, 2,3), (BACK, 2,3)])
-            ,Face::new(Colors::Orange, [(TOP, 0,1), (LEFT, 6,-3), (BOTTOM, 0,1), (RIGHT, 2,3)])
-            ,Face::new(Colors::Blue, [(TOP, 2,3), (BACK, 6,-3), (BOTTOM, 6,-3), (FRONT, 2,3)])
-            ,Face::new(Colors::Yellow, [(FRONT, 8,-1), (RIGHT, 8,-1), (BACK, 8,-1), (LEFT, 8,-1)])
-            // Fake faces, for manipulating centres, colour doesn't matter, it's never seen and represents nothing
-            ,Face::new(Colors::White, [(LEFT, 1,3), (TOP, 5,-1), (RIGHT, 7,-3), (BOTTOM, 5,-1)])
-            ,Face::new(Colors::White, [(BACK, 1,3), (TOP, 7,-3), (FRONT, 7,-3), (BOTTOM, 1,3)])
-            ,Face::new(Colors::White, [(LEFT, 5,-1), (FRONT, 5,-1), (RIGHT, 5,-1), (BACK, 5,-1)])
-        ];
-        Cube{faces:faces}
+        Cube{
+            faces: [
+                Face::new(Colors::White)
+                ,Face::new(Colors::Red)
+                ,Face::new(Colors::Green)
+                ,Face::new(Colors::Orange)
+                ,Face::new(Colors::Blue)
+                ,Face::new(Colors::Yellow)
+            ]
+            ,supercube: false
+        }
     }
 
     pub fn deserialise(&mut self, data: &str) -> Result<(), &'static str> {
-        if data.len() < 54{
+        if data.len() < 6 * S{
             return Err("not enough data, incomplete cube state");
         }
+        // Every colour is one ASCII letter, anything else can't be split up into subfaces
+        if !data.is_ascii(){
+            return Err("invalid character in cube state");
+        }
         let mut i: usize = 0;
-        for face in &mut self.faces[0..6]{
+        for face in &mut self.faces{
+            // The colours don't say which way round the stickers are, so they're all taken to be the right way
             for sface in &mut face.subfaces{
-                let col = Colors::from_shortname(&data[i..i+1]);
-                sface.color = col;
-                sface.next_color = col;
+                *sface = SubFace{color: Colors::from_shortname(&data[i..i+1]), rotation: 0};
                 i+=1;
             }
         }
@@ -317,8 +528,8 @@ impl Cube{
 
     #[cfg(not(feature="without_std"))]
     pub fn serialise(&self) -> String {
-        let mut s = String::with_capacity(54);
-        for face in &self.faces[0..6]{
+        let mut s = String::with_capacity(6 * S);
+        for face in &self.faces{
             for sface in face.subfaces{
                 s.push_str(sface.color.shortname());
             }
@@ -326,46 +537,198 @@ impl Cube{
         s
     }
 
-    pub fn twist(&mut self, twist: Twist) -> [Cube; 3]{
-        let face = twist.face;
-        let reverse = twist.reverse;
-        let mut intermediates = [*self;3];
+    // Other software reads the bottom face with the front at the top, which is upside down compared to here
+    fn standard_subface(face: usize, subface: usize) -> usize{
+        match face {
+            BOTTOM => S - 1 - subface
+            ,_ => subface
+        }
+    }
 
-        if face < FAKE_FACE_MIN {
-            let anim = (&mut self.faces[face]).twist(reverse);
-            intermediates[1].faces[face].subfaces = anim;
-            intermediates[2].faces[face].subfaces = anim;
+    // The colour of each face, from the centres. Even sized cubes don't have a centre, so they're taken to be held
+    // the way they started
+    #[cfg(not(feature="without_std"))]
+    fn face_colors(&self) -> [Colors; 6]{
+        let reference = match N % 2 {
+            1 => *self
+            ,_ => Cube::solved()
+        };
+        let mut colors = [Colors::Blank; 6];
+        for (f, color) in colors.iter_mut().enumerate(){
+            *color = reference.faces[f].subfaces[S / 2].color;
         }
+        colors
+    }
 
-        for i in 0..4{
-            let (adj, doffset, dstep) = self.faces[face].adjacent[i];
-            let (next, soffset, sstep) = self.faces[face].adjacent[((((i as isize) + if reverse {1} else {-1})+4)%4)as usize];
-            let subs = self.faces[next].subfaces;
-            let edge_anim = if !reverse {
-                self.faces[adj].copy_from(subs, doffset, dstep, soffset, sstep)
+    // The standard facelet string: faces in the order URFDLB, each sticker named by the face whose centre matches it
+    #[cfg(not(feature="without_std"))]
+    pub fn facelets(&self) -> String {
+        let colors = self.face_colors();
+        let mut s = String::with_capacity(6 * S);
+        for face in URFDLB{
+            for i in 0..S{
+                let color = self.faces[face].subfaces[Self::standard_subface(face, i)].color;
+                let name = URFDLB.iter().position(|f| colors[*f] == color).map_or('?', |n| URFDLB_NAMES[n]);
+                s.push(name);
             }
-            else {
-                self.faces[adj].copy_from(subs, doffset+(dstep*2), -dstep, soffset+(sstep*2), -sstep)
+        }
+        s
+    }
+
+    // Reads a standard facelet string, with the faces in their usual colours
+    pub fn from_facelets(data: &str) -> Result<Cube<N, S>, &'static str> {
+        let data = data.as_bytes();
+        if data.len() != 6 * S{
+            return Err("Facelet string is the wrong length");
+        }
+        let solved: Cube<N, S> = Cube::solved();
+        let mut cube = solved;
+        for (n, face) in URFDLB.iter().enumerate(){
+            for i in 0..S{
+                let name = data[n * S + i] as char;
+                let from = URFDLB_NAMES.iter().position(|c| *c == name).ok_or("Invalid facelet, must be one of URFDLB")?;
+                let color = solved.faces[URFDLB[from]].subfaces[0].color;
+                cube.faces[*face].subfaces[Self::standard_subface(*face, i)] = SubFace{color, rotation: 0};
+            }
+        }
+        Ok(cube)
+    }
+
+    // Like serialise, but with the faces in the standard URFDLB order and layout
+    #[cfg(not(feature="without_std"))]
+    pub fn serialise_standard(&self) -> String {
+        let mut s = String::with_capacity(6 * S);
+        for face in URFDLB{
+            for i in 0..S{
+                s.push_str(self.faces[face].subfaces[Self::standard_subface(face, i)].color.shortname());
+            }
+        }
+        s
+    }
+
+    // Twists the cube, and gives three frames of animation from part way through the twist
+    pub fn twist(&mut self, twist: Twist) -> [Cube<N, S>; 3]{
+        if twist.double {
+            // A half turn animates through both quarter turns
+            let quarter = Twist{double: false, ..twist};
+            let first = self.twist(quarter);
+            let halfway = *self;
+            let second = self.twist(quarter);
+            return [first[1], halfway, second[1]];
+        }
+        let (face, layers) = twist.layers(N);
+        let before = *self;
+        let mut intermediates = [*self;3];
+        // The face turns 45 degrees after the first frame, while the lines of subfaces around it slide over in two steps
+        let turned = [(0, 2), (1, 2), (1, 2)];
+        let slid = [(N / 3).max(1), (N / 3).max(1), (2 * N / 3).max(1)];
+        // The animation frames of each layer are merged into one set of frames
+        for depth in layers{
+            for (i, frame) in intermediates.iter_mut().enumerate(){
+                frame.copy_changes(&before, &before.layer_part(face, depth, twist.reverse, turned[i], (slid[i], N)));
+            }
+            self.copy_changes(&before, &before.layer_part(face, depth, twist.reverse, (1, 1), (1, 1)));
+        }
+        intermediates
+    }
+
+    // Twists the cube, and fills the buffer with frames of animation evenly spaced through the twist, so any number
+    // of frames can be had without needing to allocate
+    pub fn twist_frames(&mut self, twist: Twist, frames: &mut [Cube<N, S>]){
+        let steps = frames.len() + 1;
+        for (i, frame) in frames.iter_mut().enumerate(){
+            *frame = self.part_twisted(twist, i + 1, steps);
+        }
+        *self = self.part_twisted(twist, steps, steps);
+    }
+
+    // The cube part of the way through a twist, from 0 for not started to 1 for finished.
+    // Subfaces move in whole steps, so the smaller the cube the fewer different frames there are
+    pub fn twisted(&self, twist: Twist, progress: f32) -> Cube<N, S>{
+        const STEPS: usize = 1 << 16;
+        let done = if progress <= 0.0 {0} else if progress >= 1.0 {STEPS} else {(progress * STEPS as f32) as usize};
+        self.part_twisted(twist, done, STEPS)
+    }
+
+    // The cube `done` steps of the way through a twist that takes `steps` steps
+    fn part_twisted(&self, twist: Twist, done: usize, steps: usize) -> Cube<N, S>{
+        if twist.double {
+            // The first half of the steps are the first quarter turn
+            let quarter = Twist{double: false, ..twist};
+            return match (2 * done).checked_sub(steps) {
+                Some(second_half) => self.part_twisted(quarter, steps, steps).part_twisted(quarter, second_half, steps)
+                ,None => self.part_twisted(quarter, 2 * done, steps)
             };
-            intermediates[0].faces[adj].subfaces = edge_anim[0];
-            intermediates[1].faces[adj].subfaces = edge_anim[0];
-            intermediates[2].faces[adj].subfaces = edge_anim[1];
         }
+        let (face, layers) = twist.layers(N);
+        let mut result = *self;
+        for depth in layers{
+            result.copy_changes(self, &self.layer_part(face, depth, twist.reverse, (done, steps), (done, steps)));
+        }
+        result
+    }
 
-        for i in 0..4{
-            let (adj, _,_) = self.faces[face].adjacent[i];
-            self.faces[adj].update();
+    // Copies every subface that differs between `before` and `after` into this cube
+    fn copy_changes(&mut self, before: &Cube<N, S>, after: &Cube<N, S>){
+        for f in 0..6{
+            for s in 0..S{
+                if before.faces[f].subfaces[s] != after.faces[f].subfaces[s]{
+                    self.faces[f].subfaces[s] = after.faces[f].subfaces[s];
+                }
+            }
         }
+    }
 
+    // Subface k of the line `depth` in from the edge of a face
+    fn edge_subface(edge: Edge, depth: usize, k: usize) -> usize{
+        let far = N - 1;
+        match edge {
+            Edge::TopRow => depth * N + k
+            ,Edge::BottomRowReversed => (far - depth) * N + far - k
+            ,Edge::LeftColumnUp => (far - k) * N + depth
+            ,Edge::RightColumnDown => k * N + far - depth
+        }
+    }
 
-        #[cfg(not(feature="without_std"))]
-        for face in &self.faces{
-            for s in &face.subfaces{
-                assert!(s.color == s.next_color);
+    // One layer, `depth` layers in from the face, part of the way through a quarter turn. `turned` is how far through
+    // the turn the face itself is, and `slid` is how far the lines of subfaces around it are, both as fractions
+    fn layer_part(&self, face: usize, depth: usize, reverse: bool, turned: (usize, usize), slid: (usize, usize)) -> Cube<N, S>{
+        if depth == N - 1 {
+            // The last layer is the opposite face turning the other way
+            return self.layer_part(OPPOSITE[face], 0, !reverse, turned, slid);
+        }
+        let mut result = *self;
+
+        if depth == 0 {
+            result.faces[face].subfaces = self.faces[face].turned(reverse, turned);
+        }
+
+        let slide = N * slid.0 / slid.1;
+        let adjacent = &ADJACENT[face];
+        for i in 0..4{
+            let (adj, edge) = adjacent[i];
+            let (next, next_edge) = adjacent[(i + if reverse {1} else {3}) % 4];
+            // Going the other way, the lines are walked from the other end
+            let k_along = |k: usize| if reverse {N - 1 - k} else {k};
+            let dest = |k: usize| Cube::<N, S>::edge_subface(edge, depth, k_along(k));
+            let source = |k: usize| Cube::<N, S>::edge_subface(next_edge, depth, k_along(k));
+            // Stickers moving onto the next face turn by however much the lines they move along turn
+            let moved = |k: usize| {
+                let s = self.faces[next].subfaces[source(k)];
+                SubFace{rotation: (s.rotation + 4 + edge.direction() - next_edge.direction()) % 4, ..s}
+            };
+            for k in 0..N{
+                let j = k + slide;
+                result.faces[adj].subfaces[dest(k)] = if j < N {
+                    self.faces[adj].subfaces[dest(j)]
+                }
+                else {
+                    moved(j - N)
+                };
             }
         }
 
-        intermediates
+        result
     }
 
     #[cfg(not(feature="without_std"))]
@@ -377,6 +740,20 @@ impl Cube{
         Ok(())
     }
 
+    // Like twists, for twists separated by whitespace without brackets, but works without std. Nothing is done
+    // unless every twist is good, gives how many twists were done
+    pub fn twists_in(&mut self, twists: &str, notation: Notation) -> Result<usize, &'static str>{
+        let mut count = 0;
+        for t in Twist::iter_from_string_in(twists, notation){
+            t?;
+            count += 1;
+        }
+        for t in Twist::iter_from_string_in(twists, notation).flatten(){
+            self.twist(t);
+        }
+        Ok(count)
+    }
+
     #[cfg(not(feature="without_std"))]
     pub fn simple_string(&self) -> String{
         format!("Top:\n{}\nFront:\n{}\nLeft:\n{}\nBack:\n{}\nRight:\n{}\nBottom:\n{}"
@@ -389,36 +766,97 @@ impl Cube{
         )
     }
 
+    // A supercube is only solved when every sticker on each face is the same way round, which also allows for the
+    // whole cube being turned
     pub fn is_solved(&self) -> bool {
-        for f in 0..6{
-            let face = self.faces[f];
-            let col = face.subfaces[0].color;
-            for s in 1..9{
-                if face.subfaces[s].color != col{
+        for face in &self.faces{
+            let first = face.subfaces[0];
+            for s in 1..S{
+                if face.subfaces[s].color != first.color{
+                    return false;
+                }
+                if self.supercube && face.subfaces[s].rotation != first.rotation{
                     return false;
                 }
             }
         }
         true
     }
+
+    // How many quarter turns clockwise the centre of a face has been turned, only odd sized cubes have one centre
+    pub fn centre_rotation(&self, face: usize) -> Option<u8>{
+        match N % 2 {
+            1 => Some(self.faces[face].subfaces[S / 2].rotation)
+            ,_ => None
+        }
+    }
+}
+
+// Which way round the stickers are is only part of the state on a supercube, so two normal cubes with the same
+// colours in the same places are equal even if their centres have been turned differently
+impl<const N: usize, const S: usize> PartialEq for Cube<N, S>{
+    fn eq(&self, other: &Self) -> bool {
+        if self.supercube != other.supercube{
+            return false;
+        }
+        self.faces.iter().zip(&other.faces).all(|(a, b)|
+            a.subfaces.iter().zip(&b.subfaces).all(|(a, b)|
+                a.color == b.color && (!self.supercube || a.rotation == b.rotation)
+            )
+        )
+    }
+}
+
+impl<const N: usize, const S: usize> Eq for Cube<N, S>{}
+
+impl<const N: usize, const S: usize> Hash for Cube<N, S>{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.supercube.hash(state);
+        for face in &self.faces{
+            for s in &face.subfaces{
+                s.color.hash(state);
+                if self.supercube{
+                    s.rotation.hash(state);
+                }
+            }
+        }
+    }
+}
+
+impl Cube{
+    pub fn new() -> Cube{
+        Cube::solved()
+    }
+
+    // Checks that this state could be reached by twisting a solved cube
+    pub fn validate(&self) -> Result<(), StateError> {
+        cubie::CubieCube::validated(self).map(|_| ())
+    }
 }
 
 pub type SwitchMap5Faces = [Twist;48];
 
-pub type OutputMap5Faces = [Output;45];
+// Outputs on the five faces that have lights, there's one for each subface so the size depends on the cube
+pub type OutputMap5Faces<const OUTPUTS: usize = 45> = [Output; OUTPUTS];
+
+pub const fn outputs_5_faces(size: usize) -> usize{
+    5 * size * size
+}
 
+// Each output is the face number and then the subface number, padded so that all the subface numbers are the same length
 #[cfg(not(feature="without_std"))]
-pub fn serialise_output_map(map: &OutputMap5Faces) -> String {
+pub fn serialise_output_map(map: &[Output]) -> String {
+    let width = (map.len() / 5).saturating_sub(1).to_string().len();
     let mut result = String::new();
-    for i in 0..45{
-        result.push_str(&format!("{}{}", map[i].face, map[i].subface));
+    for output in map{
+        result.push_str(&format!("{}{:0width$}", output.face, output.subface, width = width));
     }
     result
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Cube, Twist, TOP, LEFT, CENTER_FB, BACK, BOTTOM, RIGHT};
+    use crate::{Cube, Cube2, Twist, Metric, Notation, count_moves, TOP, LEFT, CENTER_FB, BACK, BOTTOM, RIGHT};
 
     #[cfg(not(feature="without_std"))]
     #[test]
@@ -428,6 +866,38 @@ mod tests {
         assert_eq!(result, "Top:\nWWW\nWWW\nWWW\nFront:\nRRR\nRRR\nRRR\nLeft:\nGGG\nGGG\nGGG\nBack:\nOOO\nOOO\nOOO\nRight:\nBBB\nBBB\nBBB\nBottom:\nYYY\nYYY\nYYY".to_string());
     }
 
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn facelets() {
+        let mut cube = Cube::new();
+        assert_eq!(cube.facelets(), "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB");
+        assert_eq!(cube.serialise_standard(), "WWWWWWWWWBBBBBBBBBRRRRRRRRRYYYYYYYYYGGGGGGGGGOOOOOOOOO");
+        cube.twists("R").unwrap();
+        assert_eq!(cube.facelets(), "UUFUUFUUFRRRRRRRRRFFDFFDFFDDDBDDBDDBLLLLLLLLLUBBUBBUBB");
+        let mut cube = Cube::new();
+        cube.twists("U").unwrap();
+        assert_eq!(cube.facelets(), "UUUUUUUUUBBBRRRRRRRRRFFFFFFDDDDDDDDDFFFLLLLLLLLLBBBBBB");
+        assert_eq!(cube.serialise_standard(), "WWWWWWWWWOOOBBBBBBBBBRRRRRRYYYYYYYYYRRRGGGGGGGGGOOOOOO");
+        // Stickers are named after the centres, so holding the cube differently doesn't change anything
+        let mut cube = Cube::new();
+        cube.twists("x y2").unwrap();
+        assert_eq!(cube.facelets(), Cube::new().facelets());
+
+        let mut cube = Cube::new();
+        cube.twists("R U2 F' L D B2 M E' S").unwrap();
+        let read: Cube = Cube::from_facelets(&cube.facelets()).unwrap();
+        assert_eq!(read.facelets(), cube.facelets());
+        let mut cube = Cube::new();
+        cube.twists("R U2 F' L D B2").unwrap();
+        let read: Cube = Cube::from_facelets(&cube.facelets()).unwrap();
+        assert_eq!(read.serialise(), cube.serialise());
+        let mut cube = Cube2::solved();
+        cube.twists("R U F'").unwrap();
+        assert_eq!(Cube2::from_facelets(&cube.facelets()).unwrap().serialise(), cube.serialise());
+        assert!(Cube::<3, 9>::from_facelets("UUUU").is_err());
+        assert!(Cube::<3, 9>::from_facelets(&"X".repeat(54)).is_err());
+    }
+
     #[cfg(not(feature="without_std"))]
     #[test]
     fn basic_twists() {
@@ -459,43 +929,123 @@ mod tests {
         }
     }
 
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn rotations() {
+        use crate::{Colors, FRONT, ROTATE_X, ROTATE_Y, ROTATE_Z};
+        for (face, t_face, expected) in [
+            (ROTATE_X, TOP, Colors::Red)
+            ,(ROTATE_Y, FRONT, Colors::Blue)
+            ,(ROTATE_Z, TOP, Colors::Green)
+        ]{
+            let mut cube = Cube::new();
+            let anim = cube.twist(Twist{face, reverse: false, double: false, wide: false, layer: 0});
+            // Every face is still one colour, the whole cube moved together
+            for f in 0..6{
+                for s in 0..9{
+                    assert_eq!(cube.faces[f].subfaces[s].color, cube.faces[f].subfaces[0].color);
+                }
+            }
+            assert_eq!(cube.faces[t_face].subfaces[4].color, expected);
+            // the animation frames are part way through the rotation
+            assert!(!anim[0].is_solved());
+            assert!(!anim[2].is_solved());
+            cube.twist(Twist{face, reverse: true, double: false, wide: false, layer: 0});
+            assert_eq!(cube.serialise(), Cube::new().serialise());
+        }
+        let mut c = Cube::new();
+        c.twists("x y' z").expect("failed to twist");
+        c.twists("z' y x'").expect("failed to twist");
+        assert_eq!(c.serialise(), Cube::new().serialise());
+    }
+
     #[cfg(not(feature="without_std"))]
     #[test]
     fn twist_animation() {
         let mut cube = Cube::new();
-        let anim = cube.twist(Twist::from_string("u").unwrap());
+        let anim = cube.twist(Twist::from_string_in("u", Notation::Legacy).unwrap());
         assert_eq!(anim[0].simple_string(), "Top:\nWWW\nWWW\nWWW\nFront:\nRRB\nRRR\nRRR\nLeft:\nGGR\nGGG\nGGG\nBack:\nOOG\nOOO\nOOO\nRight:\nBBO\nBBB\nBBB\nBottom:\nYYY\nYYY\nYYY".to_string());
         assert_eq!(anim[1].simple_string(), "Top:\nWWW\nWWW\nWWW\nFront:\nRRB\nRRR\nRRR\nLeft:\nGGR\nGGG\nGGG\nBack:\nOOG\nOOO\nOOO\nRight:\nBBO\nBBB\nBBB\nBottom:\nYYY\nYYY\nYYY".to_string());
         assert_eq!(anim[2].simple_string(), "Top:\nWWW\nWWW\nWWW\nFront:\nRBB\nRRR\nRRR\nLeft:\nGRR\nGGG\nGGG\nBack:\nOGG\nOOO\nOOO\nRight:\nBOO\nBBB\nBBB\nBottom:\nYYY\nYYY\nYYY".to_string());
-        let anim = cube.twist(Twist::from_string("f'").unwrap());
+        let anim = cube.twist(Twist::from_string_in("f'", Notation::Legacy).unwrap());
         assert_eq!(anim[0].simple_string(), "Top:\nWWW\nWWW\nWWO\nFront:\nBBB\nRRR\nRRR\nLeft:\nRRW\nGGR\nGGG\nBack:\nGGG\nOOO\nOOO\nRight:\nBOO\nBBB\nYBB\nBottom:\nYYY\nYYY\nYYG".to_string());
         assert_eq!(anim[1].simple_string(), "Top:\nWWW\nWWW\nWWO\nFront:\nBBR\nBRR\nRRR\nLeft:\nRRW\nGGR\nGGG\nBack:\nGGG\nOOO\nOOO\nRight:\nBOO\nBBB\nYBB\nBottom:\nYYY\nYYY\nYYG".to_string());
         assert_eq!(anim[2].simple_string(), "Top:\nWWW\nWWW\nWOB\nFront:\nBBR\nBRR\nRRR\nLeft:\nRRW\nGGW\nGGR\nBack:\nGGG\nOOO\nOOO\nRight:\nBOO\nYBB\nYBB\nBottom:\nYYY\nYYY\nYGG".to_string());
     }
 
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn animation_frames(){
+        use crate::{Cube5, Colors, FRONT};
+        // Each frame of a U on a 5x5 has one more subface from the right on the top row of the front
+        let mut cube = Cube5::solved();
+        let mut frames = [Cube5::solved(); 4];
+        cube.twist_frames(Twist::from_string("U").unwrap(), &mut frames);
+        for (i, frame) in frames.iter().enumerate(){
+            let top_row = &frame.faces[FRONT].subfaces[0..5];
+            assert_eq!(top_row.iter().filter(|s| s.color == Colors::Blue).count(), i + 1);
+            assert_eq!(frame.faces[FRONT].subfaces[5].color, Colors::Red);
+        }
+        let mut expected = Cube5::solved();
+        expected.twists("U").unwrap();
+        assert_eq!(cube.serialise(), expected.serialise());
+
+        let mut cube = Cube::new();
+        cube.twists("R U F'").unwrap();
+        let start = cube.serialise();
+        for t in ["R", "U2'", "Rw'", "M", "x2"]{
+            let t = Twist::from_string(t).unwrap();
+            let mut after = cube;
+            after.twist(t);
+            assert_eq!(cube.twisted(t, 0.0).serialise(), start);
+            assert_eq!(cube.twisted(t, -1.0).serialise(), start);
+            assert_eq!(cube.twisted(t, 1.0).serialise(), after.serialise());
+            assert_ne!(cube.twisted(t, 0.99).serialise(), after.serialise());
+            let mut buffered = cube;
+            let mut frames = [Cube::new(); 11];
+            buffered.twist_frames(t, &mut frames);
+            assert_eq!(buffered.serialise(), after.serialise());
+            if t.double {
+                let mut quarter = cube;
+                quarter.twist(Twist{double: false, ..t});
+                assert_eq!(frames[5].serialise(), quarter.serialise());
+            }
+            buffered = cube;
+            buffered.twist_frames(t, &mut []);
+            assert_eq!(buffered.serialise(), after.serialise());
+        }
+        // Half way through a half turn is one quarter turn
+        let mut quarter = Cube::new();
+        quarter.twists("R").unwrap();
+        assert_eq!(Cube::new().twisted(Twist::from_string("R2").unwrap(), 0.5).serialise(), quarter.serialise());
+    }
+
     #[cfg(not(feature="without_std"))]
     #[test]
     fn ser_deser(){
         let mut c = Cube::new();
-        c.twist(Twist{face:TOP, reverse:false});
+        c.twist(Twist{face:TOP, reverse:false, double:false, wide:false, layer: 0});
         let text = c.serialise();
         assert_eq!(&text, "WWWWWWWWWBBBRRRRRRRRRGGGGGGGGGOOOOOOOOOBBBBBBYYYYYYYYY");
         let mut c = Cube::new();
         c.deserialise(&text).expect("deserialise failed");
         let text2 = c.serialise();
         assert_eq!(&text, &text2);
-        c.twist(Twist{face:TOP, reverse:true});
+        c.twist(Twist{face:TOP, reverse:true, double:false, wide:false, layer: 0});
         let text = c.serialise();
         assert_eq!(&text, "WWWWWWWWWRRRRRRRRRGGGGGGGGGOOOOOOOOOBBBBBBBBBYYYYYYYYY");
         let mut c = Cube::new();
         assert!(c.deserialise("BOOB").is_err());
+        assert!(c.deserialise(&"é".repeat(54)).is_err());
+        assert!(c.deserialise(&format!("{}é", "W".repeat(53))).is_err());
     }
 
     #[test]
     fn parse_twists(){
-        let t = Twist::from_string("t'").unwrap();
+        let t = Twist::from_string_in("t'", Notation::Legacy).unwrap();
         assert_eq!(t.face, TOP);
         assert_eq!(t.reverse, true);
+        assert!(!t.wide);
         let t = Twist::from_string("L").unwrap();
         assert_eq!(t.face, LEFT);
         assert_eq!(t.reverse, false);
@@ -508,6 +1058,19 @@ mod tests {
         let t = Twist::from_string("bO").unwrap();
         assert_eq!(t.face, BOTTOM);
         assert_eq!(t.reverse, false);
+        assert!(!t.wide);
+        let t = Twist::from_string("x").unwrap();
+        assert_eq!(t.face, crate::ROTATE_X);
+        assert!(!t.reverse);
+        let t = Twist::from_string("Z'").unwrap();
+        assert_eq!(t.face, crate::ROTATE_Z);
+        assert!(t.reverse);
+        // Which way a half turn goes doesn't make it a different twist
+        assert_eq!(Twist::from_string("R2'"), Twist::from_string("R2"));
+        assert_eq!(Twist::from_string("R2").unwrap().inverse(), Twist::from_string("R2").unwrap());
+        assert_ne!(Twist::from_string("R'"), Twist::from_string("R"));
+        assert_ne!(Twist::from_string("R2"), Twist::from_string("R"));
+        assert_ne!(Twist::from_string("R2"), Twist::from_string("L2"));
     }
 
     #[cfg(not(feature="without_std"))]
@@ -516,28 +1079,113 @@ mod tests {
         let j_b_pll = Twist::seq_from_string("R U2 R' U' R U'2 L' U R' U'").unwrap();
         let superflip1 = Twist::seq_from_string("U R2 F B R B2 R U2 L B2 R U' D' R2 F R' L B2 U2 F2").unwrap();
         let superflip2 = Twist::seq_from_string("S U B2 D2 M D' M2 S U R2 D M2 U B2 U S2").unwrap();
+        // Half turns are kept as one twist
+        assert_eq!(j_b_pll.len(), 10);
+        assert_eq!(superflip1.len(), 20);
+        assert_eq!(superflip2.len(), 16);
         // QSTM lengths:
-        assert_eq!(j_b_pll.len(), 12);
-        assert_eq!(superflip1.len(), 28);
-        assert_eq!(superflip2.len(), 23);
+        assert_eq!(count_moves(&j_b_pll, Metric::QSTM), 12);
+        assert_eq!(count_moves(&superflip1, Metric::QSTM), 28);
+        assert_eq!(count_moves(&superflip2, Metric::QSTM), 23);
+        // Other metrics
+        assert_eq!(count_moves(&j_b_pll, Metric::HTM), 10);
+        assert_eq!(count_moves(&superflip1, Metric::QTM), 28);
+        assert_eq!(count_moves(&superflip2, Metric::HTM), 22);
+        assert_eq!(count_moves(&superflip2, Metric::QTM), 32);
+        assert_eq!(count_moves(&superflip2, Metric::STM), 16);
         // Sequences
         for (a,b) in j_b_pll.iter().zip(vec![
-                Twist{face:RIGHT, reverse: false},
-                Twist{face:TOP, reverse: false},
-                Twist{face:TOP, reverse: false},
-                Twist{face:RIGHT, reverse: true},
-                Twist{face:TOP, reverse: true},
-                Twist{face:RIGHT, reverse: false},
-                Twist{face:TOP, reverse: true},
-                Twist{face:TOP, reverse: true},
-                Twist{face:LEFT, reverse: true},
-                Twist{face:TOP, reverse: false},
-                Twist{face:RIGHT, reverse: true},
-                Twist{face:TOP, reverse: true},
+                Twist{face:RIGHT, reverse: false, double: false, wide: false, layer: 0},
+                Twist{face:TOP, reverse: false, double: true, wide: false, layer: 0},
+                Twist{face:RIGHT, reverse: true, double: false, wide: false, layer: 0},
+                Twist{face:TOP, reverse: true, double: false, wide: false, layer: 0},
+                Twist{face:RIGHT, reverse: false, double: false, wide: false, layer: 0},
+                Twist{face:TOP, reverse: true, double: true, wide: false, layer: 0},
+                Twist{face:LEFT, reverse: true, double: false, wide: false, layer: 0},
+                Twist{face:TOP, reverse: false, double: false, wide: false, layer: 0},
+                Twist{face:RIGHT, reverse: true, double: false, wide: false, layer: 0},
+                Twist{face:TOP, reverse: true, double: false, wide: false, layer: 0},
             ].iter()) {
-            assert_eq!(a.face,b.face);
-            assert_eq!(a.reverse,b.reverse);
+            assert_eq!(a, b);
+        }
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn iterate_twists(){
+        let s = "R U2' Rw M\tx  2F\n";
+        let twists: Result<Vec<Twist>, _> = Twist::iter_from_string(s).collect();
+        assert_eq!(twists, Twist::seq_from_string(s));
+        assert_eq!(Twist::iter_from_string("   ").count(), 0);
+        let legacy: Vec<Twist> = Twist::iter_from_string_in("r u' f", Notation::Legacy).flatten().collect();
+        assert_eq!(legacy, Twist::seq_from_string("R U' F").unwrap());
+        let mut bad = Twist::iter_from_string("R Q U");
+        assert!(bad.next().unwrap().is_ok());
+        assert!(bad.next().unwrap().is_err());
+        assert!(bad.next().unwrap().is_ok());
+        assert!(bad.next().is_none());
+
+        let mut cube = Cube::new();
+        assert_eq!(cube.twists_in("R U R' U'", Notation::Standard), Ok(4));
+        let mut expected = Cube::new();
+        expected.twists("R U R' U'").unwrap();
+        assert_eq!(cube, expected);
+        // A bad twist anywhere means none of them are done
+        assert!(cube.twists_in("U (R U)", Notation::Standard).is_err());
+        assert_eq!(cube, expected);
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn half_turns(){
+        let mut half = Cube::new();
+        let anim = half.twist(Twist::from_string("R2").unwrap());
+        let mut quarters = Cube::new();
+        quarters.twists("R R").unwrap();
+        assert_eq!(half.serialise(), quarters.serialise());
+        // The middle frame is the cube after the first quarter turn
+        let mut quarter = Cube::new();
+        quarter.twists("R").unwrap();
+        assert_eq!(anim[1].serialise(), quarter.serialise());
+        let mut c = Cube::new();
+        c.twists("R2' M2 x2 R2").unwrap();
+        c.twists("x2 M2").unwrap();
+        assert!(c.is_solved());
+        assert_eq!(Twist::from_string("U'2"), Twist::from_string("U2'"));
+        assert!(Twist::from_string("U22").is_err());
+        assert_eq!(Twist::from_string("F2").unwrap().cost(Metric::QTM), 2);
+        assert_eq!(Twist::from_string("F2").unwrap().cost(Metric::HTM), 1);
+        assert_eq!(Twist::from_string("y2").unwrap().cost(Metric::QTM), 0);
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn wide_twists(){
+        for (wide, layers) in [
+            ("Rw", "R M'"), ("Lw", "L M"), ("Uw", "U E'"), ("Dw", "D E"), ("Fw", "F S"), ("Bw", "B S'")
+            ,("r'", "R' M"), ("u2", "U2 E2"), ("Fw2'", "F2 S2")
+        ]{
+            let mut a = Cube::new();
+            let mut b = Cube::new();
+            a.twists(wide).unwrap();
+            b.twists(layers).unwrap();
+            assert_eq!(a.serialise(), b.serialise(), "{} should be {}", wide, layers);
         }
+        let t = Twist::from_string("r").unwrap();
+        assert_eq!(t, Twist{face:RIGHT, reverse: false, double: false, wide: true, layer: 0});
+        assert_eq!(Twist::from_string("Rw"), Twist::from_string("r"));
+        let t = Twist::from_string_in("r", Notation::Legacy).unwrap();
+        assert_eq!(t, Twist{face:RIGHT, reverse: false, double: false, wide: false, layer: 0});
+        // The device protocol pads with a space
+        let t = Twist::from_string_in("f ", Notation::Legacy).unwrap();
+        assert_eq!(t, Twist{face:crate::FRONT, reverse: false, double: false, wide: false, layer: 0});
+        assert!(Twist::from_string("Mw").is_err());
+        assert!(Twist::from_string("xw").is_err());
+        assert!(Twist::from_string("rw").is_err());
+        for s in ["Rw", "Uw'", "Dw2", "Bw2'", "R", "M'", "x2"]{
+            assert_eq!(format!("{}", Twist::from_string(s).unwrap()), s);
+        }
+        assert_eq!(format!("{}", Twist::from_string("l'").unwrap()), "Lw'");
     }
 
     #[cfg(not(feature="without_std"))]
@@ -560,8 +1208,14 @@ mod tests {
     #[cfg(not(feature="without_std"))]
     #[test]
     fn test_twist_display(){
+        for s in ["2R", "3Lw'", "12B2", "Rw"]{
+            assert_eq!(format!("{}", Twist::from_string(s).unwrap()), s);
+        }
         assert_eq!("U'".to_string(), format!("{}", Twist::from_string("U'").unwrap()));
         assert_eq!("R".to_string(), format!("{}", Twist::from_string("R").unwrap()));
+        assert_eq!("y'".to_string(), format!("{}", Twist::from_string("y'").unwrap()));
+        assert_eq!("R2".to_string(), format!("{}", Twist::from_string("R2").unwrap()));
+        assert_eq!("M2'".to_string(), format!("{}", Twist::from_string("M'2").unwrap()));
     }
 
     #[cfg(not(feature="without_std"))]
@@ -581,7 +1235,164 @@ mod tests {
         let out = crate::serialise_output_map(
             &[crate::Output{face:1, subface:2};45]
         );
-        assert_eq!("121212121212121212121212121212121212121212121212121212121212121212121212121212121212121212".to_string(), out)
+        assert_eq!("121212121212121212121212121212121212121212121212121212121212121212121212121212121212121212".to_string(), out);
+        let map: crate::OutputMap5Faces<{crate::outputs_5_faces(4)}> = [crate::Output{face:3, subface:7}; 80];
+        assert_eq!(crate::serialise_output_map(&map), "307".repeat(80));
+        let map: crate::OutputMap5Faces<{crate::outputs_5_faces(2)}> = [crate::Output{face:3, subface:1}; 20];
+        assert_eq!(crate::serialise_output_map(&map), "31".repeat(20));
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn big_cubes(){
+        use crate::{Cube2, Cube4, Cube5};
+        let mut c = Cube4::solved();
+        assert!(c.is_solved());
+        assert_eq!(c.serialise().len(), 96);
+        c.twists("2R").unwrap();
+        assert!(!c.is_solved());
+        // The third column of the front has gone to the top, and nothing else on the top has moved
+        assert_eq!(c.faces[TOP].simple_string(), "WWRW\nWWRW\nWWRW\nWWRW");
+        assert_eq!(c.faces[crate::FRONT].simple_string(), "RRYR\nRRYR\nRRYR\nRRYR");
+        assert_eq!(c.faces[RIGHT].simple_string(), "BBBB\nBBBB\nBBBB\nBBBB");
+        c.twists("2R'").unwrap();
+        assert!(c.is_solved());
+        let mut d = Cube4::solved();
+        d.deserialise(&Cube4::solved().serialise()).unwrap();
+        assert!(d.is_solved());
+        assert!(d.deserialise(&Cube::new().serialise()).is_err());
+        assert_eq!(Cube2::solved().faces[BOTTOM].simple_string(), "YY\nYY");
+
+        // Every size turns the same way
+        fn check<const N: usize, const S: usize>(){
+            for (a, b) in [
+                ("x", "R M' L'".to_string()), ("y", "U E' D'".to_string()), ("z", "F S B'".to_string())
+                ,("Rw", "R 2R".to_string()), ("M2", "M' M'".to_string()), ("(R U R' U')6", "".to_string())
+                ,("2U", format!("{}D'", N - 1)), ("2F2", format!("{}B2", N - 1)), ("Lw'", format!("L' {}R", N - 1))
+            ]{
+                let mut x = Cube::<N, S>::solved();
+                let mut y = Cube::<N, S>::solved();
+                x.twists(a).unwrap();
+                y.twists(&b).unwrap();
+                assert_eq!(x.serialise(), y.serialise(), "{} should be {} on {}x{}", a, b, N, N);
+            }
+            let mut c = Cube::<N, S>::solved();
+            let sequence = "R 2U' Fw 3B2 M E' S2 Dw' y L";
+            c.twists(sequence).unwrap();
+            assert!(!c.is_solved());
+            let seq = crate::sequence::Sequence::from(Twist::seq_from_string(sequence).unwrap());
+            for t in seq.inverse().twists{
+                c.twist(t);
+            }
+            assert!(c.is_solved());
+        }
+        check::<2, 4>();
+        check::<3, 9>();
+        check::<4, 16>();
+        check::<5, 25>();
+
+        // On a 5x5 the slices are the three middle layers
+        let mut a = Cube5::solved();
+        let mut b = Cube5::solved();
+        a.twists("M").unwrap();
+        b.twists("2L 3L 4L").unwrap();
+        assert_eq!(a.serialise(), b.serialise());
+        // On a 3x3 the second layer is the slice
+        let mut a = Cube::new();
+        let mut b = Cube::new();
+        a.twists("2R 2U 2F").unwrap();
+        b.twists("M' E' S").unwrap();
+        assert_eq!(a.serialise(), b.serialise());
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn supercube(){
+        let mut c = Cube::new();
+        c.supercube = true;
+        // Only the top centre has turned, by half a turn
+        c.twists("(U R L U2 R' L')2").unwrap();
+        assert_eq!(c.centre_rotation(TOP), Some(2));
+        for f in 1..6{
+            assert_eq!(c.centre_rotation(f), Some(0));
+        }
+        assert!(!c.is_solved());
+        c.supercube = false;
+        assert!(c.is_solved());
+        c.supercube = true;
+        c.twists("(U R L U2 R' L')2").unwrap();
+        assert!(c.is_solved());
+        assert!(c.faces.iter().all(|f| f.subfaces.iter().all(|s| s.rotation == 0)));
+
+        // Turning the whole cube still counts as solved
+        c.twists("x y' z2 Rw L'").unwrap();
+        assert!(c.is_solved());
+        assert_ne!(c.centre_rotation(TOP), Some(0));
+
+        let mut c = Cube::new();
+        c.supercube = true;
+        c.twists("R").unwrap();
+        assert_eq!(c.centre_rotation(RIGHT), Some(1));
+        // Stickers going over the top onto the back end up upside down
+        assert_eq!(c.faces[BACK].subfaces[0].rotation, 2);
+        assert_eq!(c.faces[TOP].subfaces[2].rotation, 0);
+        c.twists("(R U R' U')6 R'").unwrap();
+        assert!(c.is_solved());
+        c.twists("U2 U2 D' D'").unwrap();
+        assert!(!c.is_solved());
+        assert_eq!(c.centre_rotation(BOTTOM), Some(2));
+        assert_eq!(crate::Cube4::solved().centre_rotation(TOP), None);
+
+        // A whole face of a 4x4 has to point the same way
+        let mut c = crate::Cube4::solved();
+        c.supercube = true;
+        c.twists("(R U R' U')6").unwrap();
+        assert!(c.is_solved());
+        c.twists("(U R L U2 R' L')2").unwrap();
+        assert!(!c.is_solved());
+        assert_eq!(c.faces[TOP].subfaces[5].rotation, 2);
+        c.supercube = false;
+        assert!(c.is_solved());
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn turned_centres_equality(){
+        use crate::packed::PackedCube;
+        // Every centre ends up turned, which only makes a different state on a supercube
+        let mut c = Cube::new();
+        c.twists("(R U)105").unwrap();
+        assert!(c.is_solved());
+        assert_ne!(c.centre_rotation(TOP), Some(0));
+        assert_eq!(c.serialise(), Cube::new().serialise());
+        assert_eq!(c, Cube::new());
+        let mut cubes = std::collections::HashSet::new();
+        cubes.insert(Cube::new());
+        assert!(cubes.contains(&c));
+        assert_eq!(PackedCube::try_from(&c), PackedCube::try_from(&Cube::new()));
+
+        let mut solved = Cube::new();
+        solved.supercube = true;
+        c.supercube = true;
+        assert_ne!(c, solved);
+        assert!(!cubes.contains(&c));
+        assert_ne!(PackedCube::try_from(&c), PackedCube::try_from(&solved));
+        // A supercube is never equal to a normal cube
+        assert_ne!(solved, Cube::new());
+    }
+
+    #[test]
+    fn parse_layers(){
+        assert_eq!(Twist::from_string("3Rw'"), Ok(Twist{face:RIGHT, reverse: true, double: false, wide: true, layer: 3}));
+        assert_eq!(Twist::from_string("2U2"), Ok(Twist{face:TOP, reverse: false, double: true, wide: false, layer: 2}));
+        assert_eq!(Twist::from_string("1R"), Twist::from_string("R"));
+        assert_eq!(Twist::from_string("2Rw"), Twist::from_string("Rw"));
+        assert_eq!(Twist::from_string("3r"), Twist::from_string("3Rw"));
+        assert!(Twist::from_string("2M").is_err());
+        assert!(Twist::from_string("3x").is_err());
+        assert!(Twist::from_string("0R").is_err());
+        assert!(Twist::from_string("2").is_err());
+        assert!(Twist::from_string("1234R").is_err());
     }
 }
 
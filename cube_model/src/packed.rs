@@ -0,0 +1,200 @@
+// A cube state packed into a few bytes, for keeping lots of states around, like in a search or when looking for
+// repeated states. Two packed cubes are equal when the cubes they came from have the same colours everywhere, with the
+// centres turned the same way
+//
+// The pieces are stored as their cubie coordinates: the corner and edge permutations as their position in the list of
+// all permutations, and the orientations as base 3 and base 2 digits. Twisted corners and flipped edges are kept as
+// they are, so states that can't be reached by twisting are still packed exactly, as long as there's one of each piece
+//
+// Layout, from the most significant bit of the 88 bit number:
+//   supercube (1), centre rotations (6 x 2), centre orientation (5), edge orientation (12),
+//   corner orientation (13), corner permutation (16), edge permutation (29)
+
+use crate::{Cube, StateError};
+use crate::cubie::{CubieCube, centre_orientations};
+
+pub const PACKED_BYTES: usize = 11;
+
+const CORNER_PERMUTATIONS: u128 = 40320;
+const CORNER_ORIENTATIONS: u128 = 6561;
+const EDGE_PERMUTATIONS: u128 = 479001600;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PackedCube([u8; PACKED_BYTES]);
+
+// Position of a permutation in the list of all of them in order
+fn permutation_rank(perm: &[u8]) -> u128{
+    let mut rank = 0;
+    for i in 0..perm.len(){
+        let smaller = perm[i+1..].iter().filter(|p| **p < perm[i]).count();
+        rank = rank * (perm.len() - i) as u128 + smaller as u128;
+    }
+    rank
+}
+
+fn permutation_from_rank(mut rank: u128, perm: &mut [u8]){
+    let n = perm.len();
+    let mut digits = [0; 12];
+    for i in (0..n).rev(){
+        digits[i] = (rank % (n - i) as u128) as usize;
+        rank /= (n - i) as u128;
+    }
+    let mut unused = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+    for i in 0..n{
+        perm[i] = unused[digits[i]];
+        unused.copy_within(digits[i] + 1..n - i, digits[i]);
+    }
+}
+
+fn is_permutation(perm: &[u8]) -> bool{
+    (0..perm.len() as u8).all(|p| perm.contains(&p))
+}
+
+impl PackedCube{
+    pub fn to_bytes(&self) -> [u8; PACKED_BYTES]{
+        self.0
+    }
+
+    pub fn from_bytes(bytes: [u8; PACKED_BYTES]) -> Result<PackedCube, &'static str>{
+        let packed = PackedCube(bytes);
+        let v = packed.value();
+        let valid = v & 0x1fffffff < EDGE_PERMUTATIONS
+            && (v >> 29) & 0xffff < CORNER_PERMUTATIONS
+            && (v >> 45) & 0x1fff < CORNER_ORIENTATIONS
+            && (v >> 70) & 0x1f < 24;
+        if !valid {
+            return Err("Not a packed cube");
+        }
+        Ok(packed)
+    }
+
+    fn value(&self) -> u128{
+        let mut bytes = [0; 16];
+        bytes[..PACKED_BYTES].copy_from_slice(&self.0);
+        u128::from_le_bytes(bytes)
+    }
+
+    fn from_value(v: u128) -> PackedCube{
+        let mut bytes = [0; PACKED_BYTES];
+        bytes.copy_from_slice(&v.to_le_bytes()[..PACKED_BYTES]);
+        PackedCube(bytes)
+    }
+}
+
+impl TryFrom<&Cube> for PackedCube{
+    type Error = StateError;
+
+    fn try_from(cube: &Cube) -> Result<PackedCube, StateError> {
+        let cubies = CubieCube::from_cube(cube)?;
+        if !is_permutation(&cubies.cp) {
+            return Err(StateError::DuplicateCorner);
+        }
+        if !is_permutation(&cubies.ep) {
+            return Err(StateError::DuplicateEdge);
+        }
+        let centres = centre_orientations().iter().position(|c| *c == cubies.centres).ok_or(StateError::ImpossibleCentres)?;
+        let mut v = cube.supercube as u128;
+        for face in &cube.faces{
+            // Which way round the centres are isn't part of the state of a normal cube, so it always packs as 0
+            let rotation = if cube.supercube {face.subfaces[4].rotation} else {0};
+            v = (v << 2) | rotation as u128;
+        }
+        v = (v << 5) | centres as u128;
+        v = cubies.eo.iter().fold(v, |v, o| (v << 1) | *o as u128);
+        v = (v << 13) | cubies.co.iter().fold(0, |n, o| n * 3 + *o as u128);
+        v = (v << 16) | permutation_rank(&cubies.cp);
+        v = (v << 29) | permutation_rank(&cubies.ep);
+        Ok(PackedCube::from_value(v))
+    }
+}
+
+impl From<PackedCube> for Cube{
+    // The stickers other than the centres come out the right way round, like with deserialise
+    fn from(packed: PackedCube) -> Cube {
+        let mut v = packed.value();
+        let mut cubies = CubieCube::SOLVED;
+        permutation_from_rank(v & 0x1fffffff, &mut cubies.ep);
+        v >>= 29;
+        permutation_from_rank(v & 0xffff, &mut cubies.cp);
+        v >>= 16;
+        let mut co = v & 0x1fff;
+        for o in cubies.co.iter_mut().rev(){
+            *o = (co % 3) as u8;
+            co /= 3;
+        }
+        v >>= 13;
+        for o in cubies.eo.iter_mut().rev(){
+            *o = (v & 1) as u8;
+            v >>= 1;
+        }
+        cubies.centres = centre_orientations()[(v & 0x1f) as usize];
+        v >>= 5;
+        let mut cube = cubies.to_cube();
+        for face in cube.faces.iter_mut().rev(){
+            face.subfaces[4].rotation = (v & 3) as u8;
+            v >>= 2;
+        }
+        cube.supercube = v & 1 == 1;
+        cube
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::{Cube, Colors, StateError, TOP, FRONT};
+    use super::{PackedCube, PACKED_BYTES};
+
+    fn cube(twists: &str) -> Cube{
+        let mut c = Cube::new();
+        c.twists(twists).unwrap();
+        c
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn round_trip() {
+        for s in ["", "R", "R U2 F' L D B2 M E' S x", "(R U)35", "y z'", "R U R' U' x2 E"]{
+            let c = cube(s);
+            let packed = PackedCube::try_from(&c).unwrap();
+            let unpacked = Cube::from(packed);
+            assert_eq!(unpacked.serialise(), c.serialise(), "{}", s);
+            assert_eq!(PackedCube::try_from(&unpacked), Ok(packed));
+            assert_eq!(PackedCube::from_bytes(packed.to_bytes()), Ok(packed));
+            assert_eq!(unpacked, c, "{}", s);
+        }
+        // The centres being turned round only matters on a supercube
+        let mut c = cube("(R U)105");
+        assert_eq!(PackedCube::try_from(&c), PackedCube::try_from(&Cube::new()));
+        c.supercube = true;
+        let unpacked = Cube::from(PackedCube::try_from(&c).unwrap());
+        assert_eq!(unpacked, c);
+        assert!(!unpacked.is_solved());
+        // Impossible states still pack, as long as every piece is there once
+        let mut c = Cube::new();
+        c.faces[TOP].subfaces[7].color = Colors::Red;
+        c.faces[FRONT].subfaces[1].color = Colors::White;
+        let packed = PackedCube::try_from(&c).unwrap();
+        assert_eq!(Cube::from(packed), c);
+        assert_eq!(c.validate(), Err(StateError::FlippedEdge));
+        c.faces[TOP].subfaces[7].color = Colors::Red;
+        c.faces[FRONT].subfaces[1].color = Colors::Blue;
+        assert!(PackedCube::try_from(&c).is_err());
+        assert!(PackedCube::from_bytes([0xff; PACKED_BYTES]).is_err());
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn hashing() {
+        let mut seen = HashSet::new();
+        for s in ["", "R L", "L R", "R U R' U' R U R' U' R U R' U' R U R' U' R U R' U' R U R' U'", "U", "U U U U U", "y D E"]{
+            seen.insert(PackedCube::try_from(&cube(s)).unwrap());
+        }
+        assert_eq!(seen.len(), 3);
+        // A whole cube rotation changes which way round the cube is held, which is a different state
+        assert_ne!(PackedCube::try_from(&cube("x")), PackedCube::try_from(&Cube::new()));
+        let mut cubes = HashSet::new();
+        cubes.insert(cube("R L"));
+        assert!(cubes.contains(&cube("L R")));
+    }
+}
@@ -0,0 +1,139 @@
+// Random state scrambles
+//
+// Doing a fixed number of random moves doesn't give every state the same chance of coming up.
+// Instead a state is picked uniformly from every legal state, and the scramble is the reverse of the
+// two-phase solver's solution for that state. Giving the same seed gives the same scramble.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::SystemTime;
+
+use crate::{Cube, Twist};
+use crate::cubie::{CubieCube, parity};
+use crate::solver::Solver;
+use crate::sequence::Sequence;
+
+// SplitMix64, nowhere near good enough for cryptography but plenty for picking cube states
+struct Random{
+    state: u64
+}
+
+impl Random{
+    fn new(seed: Option<u64>) -> Random{
+        Random{state: seed.unwrap_or_else(random_seed)}
+    }
+
+    fn next_u64(&mut self) -> u64{
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in 0..n, values from the short last block are thrown away so small numbers aren't more likely
+    fn below(&mut self, n: u64) -> u64{
+        let limit = u64::MAX - u64::MAX % n;
+        loop {
+            let v = self.next_u64();
+            if v < limit {
+                return v % n;
+            }
+        }
+    }
+
+    fn shuffle(&mut self, items: &mut [u8]){
+        for i in (1..items.len()).rev(){
+            let j = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+// A seed that is different every time
+pub fn random_seed() -> u64{
+    let mut hasher = RandomState::new().build_hasher();
+    if let Ok(time) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        hasher.write_u128(time.as_nanos());
+    }
+    hasher.finish()
+}
+
+fn random_cubies(seed: Option<u64>) -> CubieCube{
+    let mut rng = Random::new(seed);
+    let mut c = CubieCube::SOLVED;
+    rng.shuffle(&mut c.cp);
+    rng.shuffle(&mut c.ep);
+    // Only half of the permutations are possible, swapping two edges turns each impossible one into a possible one
+    if parity(&c.cp) != parity(&c.ep) {
+        c.ep.swap(10, 11);
+    }
+    // The last piece's orientation is fixed by the others
+    for i in 0..7{
+        c.co[i] = rng.below(3) as u8;
+    }
+    c.co[7] = (3 - c.co[0..7].iter().sum::<u8>() % 3) % 3;
+    for i in 0..11{
+        c.eo[i] = rng.below(2) as u8;
+    }
+    c.eo[11] = c.eo[0..11].iter().sum::<u8>() % 2;
+    c
+}
+
+// A uniformly random legal state
+pub fn random_state(seed: Option<u64>) -> Cube{
+    random_cubies(seed).to_cube()
+}
+
+// Twists that take a solved cube to a uniformly random state
+pub fn scramble(solver: &Solver, seed: Option<u64>) -> Vec<Twist>{
+    let solution = solver.solve(&random_state(seed)).expect("Random states are always solvable");
+    Sequence::from(solution).inverse().twists
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Cube;
+    use crate::cubie::CubieCube;
+    use crate::solver::test_solver as solver;
+    use crate::sequence::Sequence;
+    use super::{random_cubies, random_state, scramble};
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn random_states() {
+        for seed in 0..200{
+            let c = random_cubies(Some(seed));
+            assert!(c.verify().is_ok());
+            assert_eq!(CubieCube::from_cube(&c.to_cube()), Ok(c));
+        }
+        assert_eq!(random_state(Some(7)).serialise(), random_state(Some(7)).serialise());
+        assert_ne!(random_state(Some(7)).serialise(), random_state(Some(8)).serialise());
+        assert_ne!(random_state(None).serialise(), random_state(None).serialise());
+        // Every corner should turn up in every position
+        let mut seen = [[false; 8]; 8];
+        for seed in 0..200{
+            let c = random_cubies(Some(seed));
+            for (pos, corner) in c.cp.iter().enumerate(){
+                seen[pos][*corner as usize] = true;
+            }
+        }
+        assert!(seen.iter().all(|s| s.iter().all(|x| *x)));
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn scrambles() {
+        let twists = scramble(solver(), Some(42));
+        assert_eq!(twists, scramble(solver(), Some(42)));
+        let mut cube = Cube::new();
+        for t in &twists{
+            cube.twist(*t);
+        }
+        assert_eq!(cube.serialise(), random_state(Some(42)).serialise());
+        for t in Sequence::from(twists).inverse().twists{
+            cube.twist(t);
+        }
+        assert!(cube.is_solved());
+    }
+}
@@ -0,0 +1,330 @@
+// A sequence of twists, with the operations for working with algorithms and logged solves
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Twist, Metric, count_moves};
+use crate::cubie::CubieCube;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sequence{
+    pub twists: Vec<Twist>
+}
+
+impl Sequence{
+    pub fn new() -> Sequence{
+        Sequence{twists: Vec::new()}
+    }
+
+    pub fn len(&self) -> usize{
+        self.twists.len()
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.twists.is_empty()
+    }
+
+    pub fn moves(&self, metric: Metric) -> usize{
+        count_moves(&self.twists, metric)
+    }
+
+    // The sequence that undoes this one
+    pub fn inverse(&self) -> Sequence{
+        Sequence{twists: self.twists.iter().rev().map(|t| t.inverse()).collect()}
+    }
+
+    pub fn repeat(&self, times: usize) -> Sequence{
+        Sequence{twists: self.twists.repeat(times)}
+    }
+
+    // Twists of the same layer that are next to each other, or only separated by other twists on the same axis,
+    // are combined into one twist, and dropped if they cancel out. "R L R'" becomes "L", "U U U" becomes "U'"
+    pub fn simplify(&self) -> Sequence{
+        let mut twists = self.twists.clone();
+        loop {
+            let mut simplified: Vec<Twist> = Vec::with_capacity(twists.len());
+            let mut group_start = 0;
+            for t in &twists{
+                if simplified.get(group_start).is_some_and(|g| g.axis() != t.axis()) {
+                    group_start = simplified.len();
+                }
+                let same_layer = simplified[group_start..].iter().position(|g| g.face == t.face && g.wide == t.wide && g.layer == t.layer);
+                match same_layer {
+                    Some(i) => {
+                        let i = group_start + i;
+                        let turns = simplified[i].quarter_turns() + t.quarter_turns();
+                        match Twist::from_quarter_turns(t.face, turns) {
+                            Some(combined) => simplified[i] = Twist{wide: t.wide, layer: t.layer, ..combined}
+                            ,None => {
+                                simplified.remove(i);
+                            }
+                        }
+                    }
+                    ,None => simplified.push(*t)
+                }
+            }
+            if simplified == twists {
+                return Sequence{twists};
+            }
+            twists = simplified;
+        }
+    }
+
+    // How many times the sequence has to be done to get back to where it started, including the orientation
+    pub fn order(&self) -> usize{
+        let mut cubies = CubieCube::SOLVED;
+        let mut times = 0;
+        loop {
+            for t in &self.twists{
+                cubies.twist(*t);
+            }
+            times += 1;
+            if cubies == CubieCube::SOLVED {
+                return times;
+            }
+        }
+    }
+}
+
+impl From<Vec<Twist>> for Sequence{
+    fn from(twists: Vec<Twist>) -> Sequence{
+        Sequence{twists}
+    }
+}
+
+impl fmt::Display for Sequence{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, t) in self.twists.iter().enumerate(){
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", t)?;
+        }
+        Ok(())
+    }
+}
+
+// The most twists that a repeated group can expand to
+const MAX_REPEATED_TWISTS: usize = 1000;
+
+// Parses algorithms written the way they are on algorithm sheets into a flat list of twists. As well as plain twists
+// this understands commutators "[A, B]" (A B A' B'), conjugates "[A: B]" (A B A'), and groups "(A)", and any of these
+// can be nested and followed by a repeat count and/or a ' to invert it, like "(R U R' U')3" or "[R, U]'"
+pub fn parse(s: &str) -> Result<Vec<Twist>, &'static str>{
+    let mut parser = Parser{s: s.as_bytes(), pos: 0};
+    let twists = parser.sequence()?;
+    match parser.peek() {
+        None => Ok(twists)
+        ,Some(b')') | Some(b']') => Err("Unmatched closing bracket")
+        ,Some(_) => Err("Comma or colon outside of square brackets")
+    }
+}
+
+struct Parser<'a>{
+    s: &'a [u8]
+    ,pos: usize
+}
+
+impl Parser<'_>{
+    fn is_special(c: u8) -> bool{
+        matches!(c, b'(' | b')' | b'[' | b']' | b',' | b':')
+    }
+
+    // The next character that isn't whitespace
+    fn peek(&mut self) -> Option<u8>{
+        while self.s.get(self.pos).is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+        self.s.get(self.pos).copied()
+    }
+
+    // Twists and groups up until a closing bracket, comma, colon or the end
+    fn sequence(&mut self) -> Result<Vec<Twist>, &'static str>{
+        let mut twists = Vec::new();
+        loop {
+            match self.peek() {
+                None | Some(b')' | b']' | b',' | b':') => return Ok(twists)
+                ,Some(b'(') => {
+                    self.pos += 1;
+                    let group = self.sequence()?;
+                    if self.peek() != Some(b')') {
+                        return Err("Unmatched opening bracket");
+                    }
+                    self.pos += 1;
+                    twists.extend(self.suffix(group)?);
+                }
+                ,Some(b'[') => {
+                    self.pos += 1;
+                    let a = self.sequence()?;
+                    let separator = self.peek();
+                    if separator != Some(b',') && separator != Some(b':') {
+                        return Err("Square brackets need a comma or colon");
+                    }
+                    self.pos += 1;
+                    let b = self.sequence()?;
+                    if self.peek() != Some(b']') {
+                        return Err("Unmatched opening bracket");
+                    }
+                    self.pos += 1;
+                    let a_inverse = Sequence::from(a.clone()).inverse().twists;
+                    let mut expanded = a;
+                    expanded.extend_from_slice(&b);
+                    expanded.extend(a_inverse);
+                    if separator == Some(b',') {
+                        expanded.extend(Sequence::from(b).inverse().twists);
+                    }
+                    twists.extend(self.suffix(expanded)?);
+                }
+                ,Some(_) => {
+                    let start = self.pos;
+                    while self.s.get(self.pos).is_some_and(|c| !c.is_ascii_whitespace() && !Parser::is_special(*c)) {
+                        self.pos += 1;
+                    }
+                    twists.push(Twist::from_bytes(&self.s[start..self.pos])?);
+                }
+            }
+        }
+    }
+
+    // The repeat count and inverse that can come straight after a closing bracket
+    fn suffix(&mut self, twists: Vec<Twist>) -> Result<Vec<Twist>, &'static str>{
+        let start = self.pos;
+        while self.s.get(self.pos).is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let times = match self.pos - start {
+            0 => 1
+            ,_ => core::str::from_utf8(&self.s[start..self.pos]).ok()
+                .and_then(|n| n.parse::<usize>().ok())
+                .ok_or("Invalid repeat count")?
+        };
+        // The count comes straight from whoever typed it, so don't let it ask for more than can be done
+        if twists.len().checked_mul(times).is_none_or(|n| n > MAX_REPEATED_TWISTS) {
+            return Err("Repeat count too large");
+        }
+        let mut group = Sequence::from(twists).repeat(times);
+        if self.s.get(self.pos) == Some(&b'\'') {
+            self.pos += 1;
+            group = group.inverse();
+        }
+        match self.s.get(self.pos) {
+            Some(c) if !c.is_ascii_whitespace() && !Parser::is_special(*c) => Err("Invalid repeat count")
+            ,_ => Ok(group.twists)
+        }
+    }
+}
+
+impl FromStr for Sequence{
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Sequence, &'static str> {
+        Ok(Sequence{twists: parse(s)?})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Cube, Twist, Metric};
+    use super::Sequence;
+
+    fn seq(s: &str) -> Sequence{
+        s.parse().unwrap()
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn inverse_and_repeat() {
+        assert_eq!(seq("R U2 F' M x").inverse().to_string(), "x' M' F U2' R'");
+        let mut cube = Cube::new();
+        let s = seq("R U Rw' E2 y S' D");
+        for t in s.twists.iter().chain(&s.inverse().twists){
+            cube.twist(*t);
+        }
+        assert!(cube.is_solved());
+        assert_eq!(seq("R U").repeat(3), seq("R U R U R U"));
+        assert_eq!(seq("R U").repeat(0), Sequence::new());
+        assert_eq!(seq("R U R' U'").repeat(2).moves(Metric::HTM), 8);
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn simplify() {
+        assert_eq!(seq("R R'").simplify(), Sequence::new());
+        assert_eq!(seq("U U U").simplify(), seq("U'"));
+        assert_eq!(seq("U U2").simplify(), seq("U'"));
+        assert_eq!(seq("R L R'").simplify(), seq("L"));
+        assert_eq!(seq("R L R").simplify(), seq("R2 L"));
+        assert_eq!(seq("R U U' R'").simplify(), Sequence::new());
+        assert_eq!(seq("R U R'").simplify(), seq("R U R'"));
+        assert_eq!(seq("Rw R x M").simplify(), seq("Rw R x M"));
+        assert_eq!(seq("F B F' S2 B' S2 x").simplify(), seq("x"));
+        assert_eq!(seq("D E D E' y").simplify(), seq("D2 y"));
+        // Simplifying never changes what a sequence does
+        for s in ["R L R", "U U2 D E D' U", "F B F' S S B2 z"]{
+            let (mut a, mut b) = (Cube::new(), Cube::new());
+            for t in seq(s).twists{
+                a.twist(t);
+            }
+            for t in seq(s).simplify().twists{
+                b.twist(t);
+            }
+            assert_eq!(a.serialise(), b.serialise());
+        }
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn order() {
+        assert_eq!(seq("R").order(), 4);
+        assert_eq!(seq("R2").order(), 2);
+        assert_eq!(seq("R U R' U'").order(), 6);
+        assert_eq!(seq("R U").order(), 105);
+        assert_eq!(seq("x").order(), 4);
+        assert_eq!(seq("M2 E2 S2").order(), 2);
+        assert_eq!(Sequence::new().order(), 1);
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn display_round_trip() {
+        for s in ["R U2 F' Rw2' M E' S2 x y' z2", "", "Dw Bw' L2"]{
+            assert_eq!(seq(s).to_string(), s);
+            assert_eq!(seq(&seq(s).to_string()), seq(s));
+        }
+        assert!("R Q".parse::<Sequence>().is_err());
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn brackets() {
+        assert_eq!(seq("[R, U]"), seq("R U R' U'"));
+        assert_eq!(seq("[R: U]"), seq("R U R'"));
+        assert_eq!(seq("[R U : D2]"), seq("R U D2 U' R'"));
+        assert_eq!(seq("(R U R' U')3"), seq("R U R' U' R U R' U' R U R' U'"));
+        assert_eq!(seq("(R U)"), seq("R U"));
+        assert_eq!(seq("(R U)2'"), seq("U' R' U' R'"));
+        assert_eq!(seq("[R, U]'"), seq("U R U' R'"));
+        assert_eq!(seq("(R)0 U"), seq("U"));
+        assert_eq!(seq("[F: [R, U]]"), seq("F R U R' U' F'"));
+        assert_eq!(seq("[[R: U], D]"), seq("R U R' D R U' R' D'"));
+        assert_eq!(seq("R2(U F)2[L,D2]"), seq("R2 U F U F L D2 L' D2'"));
+        assert_eq!(seq("[Rw2' x: M]"), seq("Rw2' x M x' Rw2"));
+        assert_eq!(seq("((R)2 U)2"), seq("R R U R R U"));
+        // The same thing through the old interface
+        assert_eq!(Twist::seq_from_string("[R, U]").unwrap(), seq("R U R' U'").twists);
+        for bad in ["(R U", "R U)", "[R U]", "[R, U", "R, U", "[R: U: F]", "(R U)x", "(R)99999999999999999999", "[R, Q]"]{
+            assert!(bad.parse::<Sequence>().is_err(), "{} should not parse", bad);
+        }
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn repeat_limit() {
+        assert_eq!(seq("(R)1000").len(), 1000);
+        assert_eq!(seq("(R U)500").len(), 1000);
+        assert_eq!(seq("()4000000000"), Sequence::new());
+        for big in ["(R)4000000000", "(R U)501", "((R)100)11", "(R)18446744073709551615"]{
+            assert_eq!(big.parse::<Sequence>(), Err("Repeat count too large"), "{}", big);
+        }
+    }
+}
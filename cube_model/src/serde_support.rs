@@ -0,0 +1,113 @@
+// Serde support, using the same strings as everywhere else: a cube is its serialise string, a twist is its notation,
+// and a colour is its short name
+
+use std::fmt;
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::{self, Visitor};
+
+use crate::{Cube, Twist, Colors};
+
+impl<const N: usize, const S: usize> Serialize for Cube<N, S>{
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        serializer.serialize_str(&self.serialise())
+    }
+}
+
+impl<'de, const N: usize, const S: usize> Deserialize<'de> for Cube<N, S>{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Cube<N, S>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        if s.len() != 6 * S || s.chars().any(|c| c != ' ' && Colors::from_shortname(&c.to_string()) == Colors::Blank) {
+            return Err(de::Error::invalid_value(de::Unexpected::Str(&s), &"a cube state string"));
+        }
+        let mut cube = Cube::solved();
+        cube.deserialise(&s).map_err(de::Error::custom)?;
+        Ok(cube)
+    }
+}
+
+impl Serialize for Twist{
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+struct TwistVisitor;
+
+impl Visitor<'_> for TwistVisitor{
+    type Value = Twist;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a twist in standard notation")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Twist, E> {
+        Twist::from_string(s).map_err(|_| E::invalid_value(de::Unexpected::Str(s), &self))
+    }
+}
+
+impl<'de> Deserialize<'de> for Twist{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Twist, D::Error> {
+        deserializer.deserialize_str(TwistVisitor)
+    }
+}
+
+impl Serialize for Colors{
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        serializer.serialize_str(self.shortname())
+    }
+}
+
+struct ColorsVisitor;
+
+impl Visitor<'_> for ColorsVisitor{
+    type Value = Colors;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a colour short name")
+    }
+
+    // Anything that isn't a colour would read as Blank, so only a space is really blank
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Colors, E> {
+        match Colors::from_shortname(s) {
+            Colors::Blank if s != " " => Err(E::invalid_value(de::Unexpected::Str(s), &self))
+            ,color => Ok(color)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Colors{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Colors, D::Error> {
+        deserializer.deserialize_str(ColorsVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Cube, Cube2, Twist, Colors};
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn json() {
+        let mut cube = Cube::new();
+        cube.twists("R U").unwrap();
+        let json = serde_json::to_string(&cube).unwrap();
+        assert_eq!(json, format!("\"{}\"", cube.serialise()));
+        let read: Cube = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.serialise(), cube.serialise());
+        assert!(serde_json::from_str::<Cube>("\"WWW\"").is_err());
+        assert!(serde_json::from_str::<Cube>(&format!("\"{}\"", "X".repeat(54))).is_err());
+        assert!(serde_json::from_str::<Cube2>(&format!("\"{}\"", "W".repeat(24))).is_ok());
+
+        let twists = Twist::seq_from_string("R U2' Rw x 2F").unwrap();
+        let json = serde_json::to_string(&twists).unwrap();
+        assert_eq!(json, "[\"R\",\"U2'\",\"Rw\",\"x\",\"2F\"]");
+        assert_eq!(serde_json::from_str::<Vec<Twist>>(&json).unwrap(), twists);
+        assert!(serde_json::from_str::<Twist>("\"Q\"").is_err());
+
+        assert_eq!(serde_json::to_string(&Colors::Orange).unwrap(), "\"O\"");
+        assert_eq!(serde_json::from_str::<Colors>("\"G\"").unwrap(), Colors::Green);
+        assert_eq!(serde_json::from_str::<Colors>("\" \"").unwrap(), Colors::Blank);
+        assert!(serde_json::from_str::<Colors>("\"Green\"").is_err());
+    }
+}
@@ -0,0 +1,582 @@
+// Kociemba's two-phase algorithm
+//
+// Phase 1 gets the cube into the group generated by <U, D, R2, L2, F2, B2>, where every piece is oriented
+// and the middle layer edges are in the middle layer. Phase 2 then solves the cube using only those moves.
+// Both phases are iterative deepening searches over coordinates (numbers that describe part of the cube state)
+// and use pruning tables that give a lower bound on the number of moves needed to finish each phase.
+//
+// Generating the tables takes a few seconds, use `Solver::load_or_generate` to cache them in a file.
+
+use std::cmp::{max, min};
+use std::fs::File;
+use std::io::{self, Read, Write, BufWriter};
+use std::path::Path;
+
+use crate::{Cube, Twist, TOP, FRONT, LEFT, BACK, RIGHT, BOTTOM};
+use crate::cubie::CubieCube;
+
+// Move number m is a turn of MOVE_FACES[m / 3], m % 3 is 0 for a quarter turn, 1 for a half turn and 2 for an inverse turn
+const MOVE_FACES: [usize; 6] = [TOP, RIGHT, FRONT, BOTTOM, LEFT, BACK];
+const N_MOVES: usize = 18;
+// U, U2, U', R2, F2, D, D2, D', L2, B2
+const PHASE2_MOVES: [usize; 10] = [0, 1, 2, 4, 7, 9, 10, 11, 13, 16];
+const ALL_MOVES: [usize; N_MOVES] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17];
+
+const N_TWIST: usize = 2187; // 3^7 corner orientations
+const N_FLIP: usize = 2048; // 2^11 edge orientations
+const N_SLICE: usize = 495; // 12 choose 4 places for the middle layer edges
+const N_CORNERS: usize = 40320; // 8! corner permutations
+const N_UD_EDGES: usize = 40320; // 8! permutations of the top and bottom layer edges
+const N_SLICE_SORTED: usize = 24; // 4! permutations of the middle layer edges
+
+// No position needs more than this many moves in either phase
+const PHASE1_MAX_DEPTH: usize = 12;
+const PHASE2_MAX_DEPTH: usize = 18;
+
+pub const DEFAULT_MAX_LENGTH: usize = 24;
+
+const TABLE_FILE_MAGIC: &[u8; 8] = b"CUBEKOC1";
+
+pub struct Solver{
+    move_cubes: [CubieCube; N_MOVES]
+    ,twist_move: Vec<u16>
+    ,flip_move: Vec<u16>
+    ,slice_move: Vec<u16>
+    ,corners_move: Vec<u16>
+    ,ud_edges_move: Vec<u16>
+    ,slice_sorted_move: Vec<u16>
+    ,slice_twist_prune: Vec<u8>
+    ,slice_flip_prune: Vec<u8>
+    ,corners_prune: Vec<u8>
+    ,ud_edges_prune: Vec<u8>
+}
+
+fn binomial(n: usize, k: usize) -> usize{
+    if k > n {
+        return 0;
+    }
+    let mut r = 1;
+    for i in 0..k{
+        r = r * (n - i) / (i + 1);
+    }
+    r
+}
+
+// Index of a permutation of 0..n, the identity is 0
+fn perm_to_index(perm: &[u8]) -> usize{
+    let mut perm = perm.to_vec();
+    let mut index = 0;
+    for j in (1..perm.len()).rev(){
+        let mut k = 0;
+        while perm[j] as usize != j{
+            perm[0..=j].rotate_left(1);
+            k += 1;
+        }
+        index = (j + 1) * index + k;
+    }
+    index
+}
+
+fn index_to_perm(mut index: usize, perm: &mut [u8]){
+    for (i, p) in perm.iter_mut().enumerate(){
+        *p = i as u8;
+    }
+    for j in 0..perm.len(){
+        let k = index % (j + 1);
+        index /= j + 1;
+        for _ in 0..k{
+            perm[0..=j].rotate_right(1);
+        }
+    }
+}
+
+// The middle layer edges are FR, FL, BL and BR, numbered 8 to 11
+const FIRST_SLICE_EDGE: u8 = 8;
+
+impl CubieCube{
+    fn corner_twist(&self) -> usize{
+        self.co[0..7].iter().fold(0, |t, &o| 3 * t + o as usize)
+    }
+
+    fn set_corner_twist(&mut self, mut twist: usize){
+        let mut sum = 0;
+        for i in (0..7).rev(){
+            self.co[i] = (twist % 3) as u8;
+            sum += self.co[i];
+            twist /= 3;
+        }
+        self.co[7] = (3 - sum % 3) % 3;
+    }
+
+    fn flip(&self) -> usize{
+        self.eo[0..11].iter().fold(0, |f, &o| 2 * f + o as usize)
+    }
+
+    fn set_flip(&mut self, mut flip: usize){
+        let mut sum = 0;
+        for i in (0..11).rev(){
+            self.eo[i] = (flip % 2) as u8;
+            sum += self.eo[i];
+            flip /= 2;
+        }
+        self.eo[11] = sum % 2;
+    }
+
+    // Where the middle layer edges are, ignoring their order
+    fn slice(&self) -> usize{
+        let mut index = 0;
+        let mut found = 0;
+        for j in (0..12).rev(){
+            if self.ep[j] >= FIRST_SLICE_EDGE{
+                index += binomial(11 - j, found + 1);
+                found += 1;
+            }
+        }
+        index
+    }
+
+    fn set_slice(&mut self, mut index: usize){
+        let mut placed = [false; 12];
+        let mut left = 4;
+        for (j, (edge, placed)) in self.ep.iter_mut().zip(placed.iter_mut()).enumerate(){
+            if left > 0 && index >= binomial(11 - j, left){
+                *edge = FIRST_SLICE_EDGE + 4 - left as u8;
+                *placed = true;
+                index -= binomial(11 - j, left);
+                left -= 1;
+            }
+        }
+        let mut other = 0;
+        for (edge, placed) in self.ep.iter_mut().zip(placed){
+            if !placed{
+                *edge = other;
+                other += 1;
+            }
+        }
+    }
+
+    fn corners(&self) -> usize{
+        perm_to_index(&self.cp)
+    }
+
+    fn set_corners(&mut self, index: usize){
+        index_to_perm(index, &mut self.cp);
+    }
+
+    // Only meaningful when the middle layer edges are in the middle layer
+    fn ud_edges(&self) -> usize{
+        perm_to_index(&self.ep[0..8])
+    }
+
+    fn set_ud_edges(&mut self, index: usize){
+        index_to_perm(index, &mut self.ep[0..8]);
+        for i in 8..12{
+            self.ep[i] = i as u8;
+        }
+    }
+
+    // Only meaningful when the middle layer edges are in the middle layer
+    fn slice_sorted(&self) -> usize{
+        let mut perm = [0; 4];
+        for (p, edge) in perm.iter_mut().zip(&self.ep[8..12]){
+            *p = edge - FIRST_SLICE_EDGE;
+        }
+        perm_to_index(&perm)
+    }
+
+    fn set_slice_sorted(&mut self, index: usize){
+        for i in 0..8{
+            self.ep[i] = i as u8;
+        }
+        index_to_perm(index, &mut self.ep[8..12]);
+        for i in 8..12{
+            self.ep[i] += FIRST_SLICE_EDGE;
+        }
+    }
+}
+
+fn move_cubes() -> [CubieCube; N_MOVES]{
+    let mut cubes = [CubieCube::SOLVED; N_MOVES];
+    for (f, face) in MOVE_FACES.iter().enumerate(){
+        let quarter = CubieCube::face_move(*face);
+        let mut c = CubieCube::SOLVED;
+        for power in 0..3{
+            c = c.multiply(&quarter);
+            cubes[f * 3 + power] = c;
+        }
+    }
+    cubes
+}
+
+fn move_twist(m: usize) -> Twist{
+    Twist{
+        face: MOVE_FACES[m / 3]
+        ,reverse: m % 3 == 2
+        ,double: m % 3 == 1
+        ,wide: false
+        ,layer: 0
+    }
+}
+
+// table[coord * N_MOVES + m] is the coordinate after doing move m
+fn move_table(move_cubes: &[CubieCube; N_MOVES], size: usize, moves: &[usize], set: fn(&mut CubieCube, usize), get: fn(&CubieCube) -> usize) -> Vec<u16>{
+    let mut table = vec![0; size * N_MOVES];
+    for i in 0..size{
+        let mut c = CubieCube::SOLVED;
+        set(&mut c, i);
+        for &m in moves{
+            table[i * N_MOVES + m] = get(&c.multiply(&move_cubes[m])) as u16;
+        }
+    }
+    table
+}
+
+// Breadth first search out from the solved state over a pair of coordinates,
+// table[a * size_b + b] is the number of moves needed to get both coordinates back to 0
+fn prune_table(move_a: &[u16], size_a: usize, move_b: &[u16], size_b: usize, moves: &[usize]) -> Vec<u8>{
+    let mut table = vec![u8::MAX; size_a * size_b];
+    let mut queue = Vec::with_capacity(size_a * size_b);
+    table[0] = 0;
+    queue.push(0);
+    let mut next = 0;
+    while next < queue.len(){
+        let index = queue[next];
+        next += 1;
+        let (a, b) = (index / size_b, index % size_b);
+        for &m in moves{
+            let moved = move_a[a * N_MOVES + m] as usize * size_b + move_b[b * N_MOVES + m] as usize;
+            if table[moved] == u8::MAX{
+                table[moved] = table[index] + 1;
+                queue.push(moved);
+            }
+        }
+    }
+    table
+}
+
+struct Search<'a>{
+    solver: &'a Solver
+    ,start: CubieCube
+    ,moves: Vec<usize>
+    ,max_length: usize
+}
+
+impl<'a> Search<'a>{
+    // Turning the same face twice in a row, or opposite faces in both orders, never helps
+    fn redundant(&self, m: usize) -> bool{
+        match self.moves.last() {
+            None => false
+            ,Some(last) => {
+                let (face, last_face) = (m / 3, last / 3);
+                face == last_face || (face % 3 == last_face % 3 && face < last_face)
+            }
+        }
+    }
+
+    fn phase1(&mut self, twist: usize, flip: usize, slice: usize, togo: usize) -> bool{
+        let s = self.solver;
+        if togo == 0 {
+            // Ending phase 1 on a phase 2 move would just repeat a shorter phase 1 solution
+            let ends_on_phase2_move = self.moves.last().is_some_and(|m| PHASE2_MOVES.contains(m));
+            return twist == 0 && flip == 0 && slice == 0 && !ends_on_phase2_move && self.start_phase2();
+        }
+        for m in ALL_MOVES{
+            if self.redundant(m) {
+                continue;
+            }
+            let twist = s.twist_move[twist * N_MOVES + m] as usize;
+            let flip = s.flip_move[flip * N_MOVES + m] as usize;
+            let slice = s.slice_move[slice * N_MOVES + m] as usize;
+            let dist = max(s.slice_twist_prune[slice * N_TWIST + twist], s.slice_flip_prune[slice * N_FLIP + flip]);
+            if dist as usize >= togo {
+                continue;
+            }
+            self.moves.push(m);
+            if self.phase1(twist, flip, slice, togo - 1) {
+                return true;
+            }
+            self.moves.pop();
+        }
+        false
+    }
+
+    fn start_phase2(&mut self) -> bool{
+        let s = self.solver;
+        let mut c = self.start;
+        for &m in &self.moves{
+            c = c.multiply(&s.move_cubes[m]);
+        }
+        let (corners, ud_edges, slice_sorted) = (c.corners(), c.ud_edges(), c.slice_sorted());
+        let longest = min(self.max_length - self.moves.len(), PHASE2_MAX_DEPTH);
+        let shortest = max(
+            s.corners_prune[corners * N_SLICE_SORTED + slice_sorted]
+            ,s.ud_edges_prune[ud_edges * N_SLICE_SORTED + slice_sorted]
+        ) as usize;
+        for depth in shortest..=longest{
+            if self.phase2(corners, ud_edges, slice_sorted, depth) {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn phase2(&mut self, corners: usize, ud_edges: usize, slice_sorted: usize, togo: usize) -> bool{
+        let s = self.solver;
+        if togo == 0 {
+            return corners == 0 && ud_edges == 0 && slice_sorted == 0;
+        }
+        for m in PHASE2_MOVES{
+            if self.redundant(m) {
+                continue;
+            }
+            let corners = s.corners_move[corners * N_MOVES + m] as usize;
+            let ud_edges = s.ud_edges_move[ud_edges * N_MOVES + m] as usize;
+            let slice_sorted = s.slice_sorted_move[slice_sorted * N_MOVES + m] as usize;
+            let dist = max(
+                s.corners_prune[corners * N_SLICE_SORTED + slice_sorted]
+                ,s.ud_edges_prune[ud_edges * N_SLICE_SORTED + slice_sorted]
+            );
+            if dist as usize >= togo {
+                continue;
+            }
+            self.moves.push(m);
+            if self.phase2(corners, ud_edges, slice_sorted, togo - 1) {
+                return true;
+            }
+            self.moves.pop();
+        }
+        false
+    }
+}
+
+impl Solver{
+    pub fn generate() -> Solver{
+        let move_cubes = move_cubes();
+        let twist_move = move_table(&move_cubes, N_TWIST, &ALL_MOVES, CubieCube::set_corner_twist, CubieCube::corner_twist);
+        let flip_move = move_table(&move_cubes, N_FLIP, &ALL_MOVES, CubieCube::set_flip, CubieCube::flip);
+        let slice_move = move_table(&move_cubes, N_SLICE, &ALL_MOVES, CubieCube::set_slice, CubieCube::slice);
+        let corners_move = move_table(&move_cubes, N_CORNERS, &PHASE2_MOVES, CubieCube::set_corners, CubieCube::corners);
+        let ud_edges_move = move_table(&move_cubes, N_UD_EDGES, &PHASE2_MOVES, CubieCube::set_ud_edges, CubieCube::ud_edges);
+        let slice_sorted_move = move_table(&move_cubes, N_SLICE_SORTED, &PHASE2_MOVES, CubieCube::set_slice_sorted, CubieCube::slice_sorted);
+        let slice_twist_prune = prune_table(&slice_move, N_SLICE, &twist_move, N_TWIST, &ALL_MOVES);
+        let slice_flip_prune = prune_table(&slice_move, N_SLICE, &flip_move, N_FLIP, &ALL_MOVES);
+        let corners_prune = prune_table(&corners_move, N_CORNERS, &slice_sorted_move, N_SLICE_SORTED, &PHASE2_MOVES);
+        let ud_edges_prune = prune_table(&ud_edges_move, N_UD_EDGES, &slice_sorted_move, N_SLICE_SORTED, &PHASE2_MOVES);
+        Solver{
+            move_cubes
+            ,twist_move
+            ,flip_move
+            ,slice_move
+            ,corners_move
+            ,ud_edges_move
+            ,slice_sorted_move
+            ,slice_twist_prune
+            ,slice_flip_prune
+            ,corners_prune
+            ,ud_edges_prune
+        }
+    }
+
+    fn move_tables(&self) -> [&Vec<u16>; 6]{
+        [&self.twist_move, &self.flip_move, &self.slice_move, &self.corners_move, &self.ud_edges_move, &self.slice_sorted_move]
+    }
+
+    fn prune_tables(&self) -> [&Vec<u8>; 4]{
+        [&self.slice_twist_prune, &self.slice_flip_prune, &self.corners_prune, &self.ud_edges_prune]
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()>{
+        let mut f = BufWriter::new(File::create(path)?);
+        f.write_all(TABLE_FILE_MAGIC)?;
+        for table in self.move_tables(){
+            for v in table{
+                f.write_all(&v.to_le_bytes())?;
+            }
+        }
+        for table in self.prune_tables(){
+            f.write_all(table)?;
+        }
+        f.flush()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Solver>{
+        let mut data = Vec::new();
+        File::open(path)?.read_to_end(&mut data)?;
+        let bad_file = || io::Error::new(io::ErrorKind::InvalidData, "Not a solver table file");
+        if !data.starts_with(TABLE_FILE_MAGIC){
+            return Err(bad_file());
+        }
+        let mut solver = Solver{
+            move_cubes: move_cubes()
+            ,twist_move: vec![0; N_TWIST * N_MOVES]
+            ,flip_move: vec![0; N_FLIP * N_MOVES]
+            ,slice_move: vec![0; N_SLICE * N_MOVES]
+            ,corners_move: vec![0; N_CORNERS * N_MOVES]
+            ,ud_edges_move: vec![0; N_UD_EDGES * N_MOVES]
+            ,slice_sorted_move: vec![0; N_SLICE_SORTED * N_MOVES]
+            ,slice_twist_prune: vec![0; N_SLICE * N_TWIST]
+            ,slice_flip_prune: vec![0; N_SLICE * N_FLIP]
+            ,corners_prune: vec![0; N_CORNERS * N_SLICE_SORTED]
+            ,ud_edges_prune: vec![0; N_UD_EDGES * N_SLICE_SORTED]
+        };
+        let expected = TABLE_FILE_MAGIC.len()
+            + solver.move_tables().iter().map(|t| t.len() * 2).sum::<usize>()
+            + solver.prune_tables().iter().map(|t| t.len()).sum::<usize>();
+        if data.len() != expected{
+            return Err(bad_file());
+        }
+        let mut pos = TABLE_FILE_MAGIC.len();
+        for table in [&mut solver.twist_move, &mut solver.flip_move, &mut solver.slice_move, &mut solver.corners_move, &mut solver.ud_edges_move, &mut solver.slice_sorted_move]{
+            for v in table.iter_mut(){
+                *v = u16::from_le_bytes([data[pos], data[pos + 1]]);
+                pos += 2;
+            }
+        }
+        for table in [&mut solver.slice_twist_prune, &mut solver.slice_flip_prune, &mut solver.corners_prune, &mut solver.ud_edges_prune]{
+            let len = table.len();
+            table.copy_from_slice(&data[pos..pos + len]);
+            pos += len;
+        }
+        // The file could be stale or corrupted, and a coordinate out of range would panic in the middle of a solve
+        let move_sizes = [N_TWIST, N_FLIP, N_SLICE, N_CORNERS, N_UD_EDGES, N_SLICE_SORTED];
+        for (table, size) in solver.move_tables().iter().zip(move_sizes){
+            if table.iter().any(|v| *v as usize >= size){
+                return Err(bad_file());
+            }
+        }
+        let max_depths = [PHASE1_MAX_DEPTH, PHASE1_MAX_DEPTH, PHASE2_MAX_DEPTH, PHASE2_MAX_DEPTH];
+        for (table, max_depth) in solver.prune_tables().iter().zip(max_depths){
+            if table[0] != 0 || table.iter().any(|d| *d as usize > max_depth){
+                return Err(bad_file());
+            }
+        }
+        Ok(solver)
+    }
+
+    // Loads the tables from the file, or generates them and saves them to the file if it can't be loaded
+    pub fn load_or_generate<P: AsRef<Path>>(path: P) -> Solver{
+        match Solver::load(&path) {
+            Ok(solver) => solver
+            ,Err(_) => {
+                let solver = Solver::generate();
+                // If the cache can't be written the tables will just be generated again next time
+                let _ignored = solver.save(&path);
+                solver
+            }
+        }
+    }
+
+    pub fn solve(&self, cube: &Cube) -> Result<Vec<Twist>, &'static str>{
+        self.solve_with_max_length(cube, DEFAULT_MAX_LENGTH)
+    }
+
+    // Finds a solution of at most max_length face turns (half turns count as one)
+    // The solution is not necessarily optimal, smaller limits give shorter solutions but take longer to find
+    pub fn solve_with_max_length(&self, cube: &Cube, max_length: usize) -> Result<Vec<Twist>, &'static str>{
+        let start = CubieCube::validated(cube).map_err(|e| e.message())?;
+        let mut search = Search{
+            solver: self
+            ,start
+            ,moves: Vec::new()
+            ,max_length
+        };
+        let (twist, flip, slice) = (start.corner_twist(), start.flip(), start.slice());
+        for depth in 0..=min(max_length, PHASE1_MAX_DEPTH){
+            if search.phase1(twist, flip, slice, depth) {
+                return Ok(search.moves.iter().map(|&m| move_twist(m)).collect());
+            }
+        }
+        Err("No solution found within the maximum length")
+    }
+}
+
+// Generating the tables is slow without optimisations, so all the tests share one solver
+#[cfg(test)]
+pub(crate) fn test_solver() -> &'static Solver{
+    static SOLVER: std::sync::OnceLock<Solver> = std::sync::OnceLock::new();
+    SOLVER.get_or_init(Solver::generate)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Cube;
+    use crate::cubie::CubieCube;
+    use super::{Solver, perm_to_index, index_to_perm, test_solver as solver};
+
+    fn check_solves(scramble: &str){
+        let mut cube = Cube::new();
+        cube.twists(scramble).unwrap();
+        let solution = solver().solve(&cube).expect("no solution");
+        assert!(solution.len() <= super::DEFAULT_MAX_LENGTH);
+        for t in solution{
+            cube.twist(t);
+        }
+        assert!(cube.is_solved(), "failed to solve {}", scramble);
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn coordinates() {
+        let mut perm = [0; 8];
+        for i in [0, 1, 5039, 40319]{
+            index_to_perm(i, &mut perm);
+            assert_eq!(perm_to_index(&perm), i);
+        }
+        let mut c = CubieCube::SOLVED;
+        for i in 0..495{
+            c.set_slice(i);
+            assert_eq!(c.slice(), i);
+        }
+        for i in [0, 1000, 2186]{
+            c.set_corner_twist(i);
+            assert_eq!(c.corner_twist(), i);
+            assert!(c.verify().is_ok());
+        }
+        let solved = CubieCube::SOLVED;
+        assert_eq!((solved.corner_twist(), solved.flip(), solved.slice(), solved.corners(), solved.ud_edges(), solved.slice_sorted()), (0, 0, 0, 0, 0, 0));
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn solves() {
+        let mut cube = Cube::new();
+        assert_eq!(solver().solve(&cube), Ok(vec![]));
+        check_solves("R");
+        check_solves("R U R' U' F2 D L' B");
+        check_solves("D' R2 B2 L2 F2 D2 U' L2 U' F' R' U2 B' F' L2 B' U F' D' U'");
+        // superflip
+        check_solves("U R2 F B R B2 R U2 L B2 R U' D' R2 F R' L B2 U2 F2");
+        // slices and rotations move the centres, the solution is relative to wherever the centres are
+        check_solves("M E S x R U y' F2 M2 D");
+        cube.deserialise("WWWWWWWWBRRWRRRRRRGGGGGGGGGOOOOOOOOORBBBBBBBBYYYYYYYYY").unwrap();
+        assert!(solver().solve(&cube).is_err());
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn cached_tables() {
+        let path = std::env::temp_dir().join(format!("cube_solver_tables_{}", std::process::id()));
+        solver().save(&path).unwrap();
+        let loaded = Solver::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let mut cube = Cube::new();
+        cube.twists("F R U' L2 B").unwrap();
+        let solution = loaded.solve(&cube).unwrap();
+        assert_eq!(solution, solver().solve(&cube).unwrap());
+        assert!(Solver::load(std::env::temp_dir().join("no_such_cube_tables")).is_err());
+
+        // A file of the right length with a move out of range, or an impossible prune depth, isn't loaded
+        solver().save(&path).unwrap();
+        let mut data = std::fs::read(&path).unwrap();
+        let good = data.clone();
+        data[8] = 0xff;
+        data[9] = 0xff;
+        std::fs::write(&path, &data).unwrap();
+        assert_eq!(Solver::load(&path).err().map(|e| e.kind()), Some(std::io::ErrorKind::InvalidData));
+        data = good;
+        *data.last_mut().unwrap() = 200;
+        std::fs::write(&path, &data).unwrap();
+        assert_eq!(Solver::load(&path).err().map(|e| e.kind()), Some(std::io::ErrorKind::InvalidData));
+        let _ = std::fs::remove_file(&path);
+    }
+}
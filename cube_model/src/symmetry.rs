@@ -0,0 +1,215 @@
+// The 48 symmetries of the cube, for changing algorithms to be done from a different side or in a mirror
+//
+// A symmetry changes which face each twist is done on, and a mirror also changes which way round every twist goes.
+// Applying a rotation symmetry to an algorithm gives the algorithm that does the same as doing the rotation, then the
+// algorithm, then undoing the rotation, but without turning the whole cube
+
+use crate::{Twist, TOP, FRONT, LEFT, BACK, RIGHT, BOTTOM, OPPOSITE, FAKE_FACE_MIN, CENTER_FB, CENTER_LR, CENTER_BT, ROTATION_MIN, ROTATE_X, ROTATE_Y, ROTATE_Z};
+use crate::cubie::ROTATION_CENTRES;
+
+// Slices and rotations turn the same way as one of the faces
+const FOLLOWS: [(usize, usize); 6] = [
+    (CENTER_FB, FRONT)
+    ,(CENTER_LR, LEFT)
+    ,(CENTER_BT, BOTTOM)
+    ,(ROTATE_X, RIGHT)
+    ,(ROTATE_Y, TOP)
+    ,(ROTATE_Z, FRONT)
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Symmetry{
+    // The face that twists of each face become
+    faces: [usize; 6]
+    // Swapped left and right, so every twist turns the other way
+    ,mirror: bool
+}
+
+impl Symmetry{
+    pub const IDENTITY: Symmetry = Symmetry{faces: [TOP, FRONT, LEFT, BACK, RIGHT, BOTTOM], mirror: false};
+    pub const MIRROR: Symmetry = Symmetry{faces: [TOP, FRONT, RIGHT, BACK, LEFT, BOTTOM], mirror: true};
+
+    // Does other first, and then this
+    pub fn after(&self, other: &Symmetry) -> Symmetry{
+        let mut faces = other.faces;
+        for face in &mut faces{
+            *face = self.faces[*face];
+        }
+        Symmetry{faces, mirror: self.mirror != other.mirror}
+    }
+
+    pub fn inverse(&self) -> Symmetry{
+        let mut faces = self.faces;
+        for (f, face) in self.faces.iter().enumerate(){
+            faces[*face] = f;
+        }
+        Symmetry{faces, mirror: self.mirror}
+    }
+
+    // The symmetry that turns an algorithm to be done with the cube held as it is after these rotations,
+    // "x" makes an algorithm for the front face into one for the bottom face
+    pub fn from_rotations(rotations: &[Twist]) -> Result<Symmetry, &'static str>{
+        let mut symmetry = Symmetry::IDENTITY;
+        for r in rotations{
+            if r.face < ROTATION_MIN {
+                return Err("Only whole cube rotations can change the way an algorithm is held");
+            }
+            for _ in 0..r.quarter_turns(){
+                let turn = Symmetry{faces: ROTATION_CENTRES[r.face - ROTATE_X], mirror: false};
+                symmetry = symmetry.after(&turn);
+            }
+        }
+        Ok(symmetry)
+    }
+
+    // Every symmetry, the 24 ways of holding the cube, then the same again in a mirror. The first is the identity
+    pub fn all() -> Vec<Symmetry>{
+        let x = Symmetry{faces: ROTATION_CENTRES[0], mirror: false};
+        let y = Symmetry{faces: ROTATION_CENTRES[1], mirror: false};
+        let mut all = vec![Symmetry::IDENTITY];
+        let mut i = 0;
+        while i < all.len(){
+            for turn in [x, y]{
+                let s = all[i].after(&turn);
+                if !all.contains(&s) {
+                    all.push(s);
+                }
+            }
+            i += 1;
+        }
+        let mirrored: Vec<Symmetry> = all.iter().map(|s| s.after(&Symmetry::MIRROR)).collect();
+        all.extend(mirrored);
+        all
+    }
+
+    pub fn apply(&self, twist: Twist) -> Twist{
+        let reverse = twist.reverse != self.mirror;
+        if twist.face < FAKE_FACE_MIN {
+            return Twist{face: self.faces[twist.face], reverse, ..twist};
+        }
+        // Find the slice or rotation that follows the face that this one's face became
+        let follows = FOLLOWS.iter().find(|(f, _)| *f == twist.face).map(|(_, follows)| self.faces[*follows]).unwrap_or(twist.face);
+        let same_kind = |f: usize| (f >= ROTATION_MIN) == (twist.face >= ROTATION_MIN);
+        match FOLLOWS.iter().find(|(f, face)| same_kind(*f) && (*face == follows || *face == OPPOSITE[follows])) {
+            Some((face, f)) => Twist{face: *face, reverse: reverse != (*f != follows), ..twist}
+            ,None => twist
+        }
+    }
+
+    pub fn apply_all(&self, twists: &[Twist]) -> Vec<Twist>{
+        twists.iter().map(|t| self.apply(*t)).collect()
+    }
+}
+
+// The algorithm that does the mirror image of this one, left and right are swapped
+pub fn mirror(twists: &[Twist]) -> Vec<Twist>{
+    Symmetry::MIRROR.apply_all(twists)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Cube, Colors, Twist, TOP, FRONT, LEFT, BACK, RIGHT, BOTTOM};
+    use crate::sequence::Sequence;
+    use super::{Symmetry, mirror};
+
+    fn seq(s: &str) -> Vec<Twist>{
+        Twist::seq_from_string(s).unwrap()
+    }
+
+    fn cube(twists: &[Twist]) -> Cube{
+        let mut c = Cube::new();
+        for t in twists{
+            c.twist(*t);
+        }
+        c
+    }
+
+    // What the cube looks like in a mirror held to its left or right side
+    fn mirrored(cube: &Cube) -> Cube{
+        let mut result = *cube;
+        for (from, to) in [(TOP, TOP), (FRONT, FRONT), (LEFT, RIGHT), (BACK, BACK), (RIGHT, LEFT), (BOTTOM, BOTTOM)]{
+            for s in 0..9{
+                let mut subface = cube.faces[from].subfaces[s];
+                subface.color = match subface.color {
+                    Colors::Green => Colors::Blue
+                    ,Colors::Blue => Colors::Green
+                    ,c => c
+                };
+                result.faces[to].subfaces[s - s % 3 + 2 - s % 3].color = subface.color;
+            }
+        }
+        result
+    }
+
+    const ALGORITHMS: [&str; 5] = [
+        "R U R' U'"
+        ,"F R U' R' U' R U R' F' R U R' U' R' F R F'"
+        ,"M2 U M U2 M' U M2"
+        ,"Rw U2 x S' E2 y' D B2 L' z"
+        ,"R2 Lw' Fw Dw2 Uw' Bw"
+    ];
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn mirror_images() {
+        assert_eq!(mirror(&seq("R U R' U'")), seq("L' U' L U"));
+        assert_eq!(mirror(&seq("M2 U M U2 M' U M2")), seq("M2 U' M U2' M' U' M2"));
+        assert_eq!(mirror(&seq("x y z Rw E S")), seq("x y' z' Lw' E' S'"));
+        for a in ALGORITHMS{
+            let a = seq(a);
+            assert_eq!(mirrored(&cube(&a)).serialise(), cube(&mirror(&a)).serialise(), "{:?}", a);
+            assert_eq!(mirror(&mirror(&a)), a);
+        }
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn rotations() {
+        assert_eq!(Symmetry::from_rotations(&seq("y")).unwrap().apply_all(&seq("R U F")), seq("B U R"));
+        assert_eq!(Symmetry::from_rotations(&seq("x")).unwrap().apply_all(&seq("R U F M")), seq("R F D M"));
+        assert!(Symmetry::from_rotations(&seq("x R")).is_err());
+        // Every way of holding the cube, from up to three rotations
+        let mut rotations = Vec::new();
+        for a in ["", "x", "x2", "x'", "z", "z'"]{
+            for b in ["", "y", "y2", "y'"]{
+                rotations.push(seq(&format!("{} {}", a, b)));
+            }
+        }
+        for r in &rotations{
+            let symmetry = Symmetry::from_rotations(r).unwrap();
+            let undo = Sequence::from(r.clone()).inverse().twists;
+            for a in ALGORITHMS{
+                let a = seq(a);
+                let conjugated: Vec<Twist> = r.iter().chain(&a).chain(&undo).copied().collect();
+                assert_eq!(cube(&symmetry.apply_all(&a)).serialise(), cube(&conjugated).serialise(), "{:?} {:?}", r, a);
+                assert_eq!(symmetry.inverse().apply_all(&symmetry.apply_all(&a)), a);
+            }
+        }
+    }
+
+    #[cfg(not(feature="without_std"))]
+    #[test]
+    fn all_symmetries() {
+        let all = Symmetry::all();
+        assert_eq!(all.len(), 48);
+        assert_eq!(all[0], Symmetry::IDENTITY);
+        for (i, s) in all.iter().enumerate(){
+            assert!(!all[i+1..].contains(s));
+            assert!(all.contains(&s.inverse()));
+            assert_eq!(s.after(&s.inverse()), Symmetry::IDENTITY);
+            // A symmetry keeps twists as long as they were and doesn't change what they do to a solved cube
+            for a in ALGORITHMS{
+                let a = seq(a);
+                let changed = s.apply_all(&a);
+                assert_eq!(Sequence::from(changed.clone()).order(), Sequence::from(a.clone()).order());
+                assert_eq!(cube(&changed).is_solved(), cube(&a).is_solved());
+            }
+        }
+        // Mirrored symmetries are a rotation after a mirror
+        for s in &all[24..]{
+            let rotation = s.after(&Symmetry::MIRROR);
+            let a = seq(ALGORITHMS[1]);
+            assert_eq!(s.apply_all(&a), rotation.apply_all(&mirror(&a)));
+        }
+    }
+}
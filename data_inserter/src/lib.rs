@@ -41,8 +41,15 @@ pub async fn main(req: Request, env: Env, _ctx: worker::Context) -> Result<Respo
                 console_log!("datapoint written to clickhouse successfully");
             }
 
-            if let Datapoint::GameSolve(game_solve) = datapoint {
-                let seconds: u32 = game_solve.play_time_milliseconds / 1000;
+            // A DNF has no time, so there's nothing to tweet about
+            let game_solve = match datapoint {
+                Datapoint::GameSolve(game_solve) => Some(game_solve),
+                _ => None,
+            };
+            if let Some((game_solve, milliseconds)) =
+                game_solve.and_then(|g| g.play_time_milliseconds.map(|ms| (g, ms)))
+            {
+                let seconds: u32 = milliseconds / 1000;
                 let minutes = seconds / 60;
                 let elapsed;
                 if minutes > 1 {
@@ -27,7 +27,13 @@ CREATE TABLE twists (
 
 CREATE TABLE game_solves (
     game_id String COMMENT 'Unique identifier of this game',
-    play_time_milliseconds UInt32 COMMENT 'How long the solve took, in milliseconds',
+    play_time_milliseconds Nullable(UInt32) COMMENT 'How long the solve took, in milliseconds, with the penalty added (null for a DNF)',
+    raw_play_time_milliseconds UInt32 COMMENT 'How long the solve took from the first twist, in milliseconds, without the penalty',
+    penalty String COMMENT 'WCA inspection penalty: OK, +2 (started after 15s) or DNF (started after 17s)',
+    cross_milliseconds Nullable(UInt32) COMMENT 'How far into the solve the cross was first done, in milliseconds',
+    f2l_milliseconds Nullable(UInt32) COMMENT 'How far into the solve the first two layers were first done',
+    oll_milliseconds Nullable(UInt32) COMMENT 'How far into the solve the last layer was first oriented',
+    pll_milliseconds Nullable(UInt32) COMMENT 'How far into the solve the cube was solved',
     new_top_score Bool COMMENT 'Whether the cube recognised this as a new top score',
     cube_state String COMMENT 'Solved cube face positions',
     timestamp DateTime64(6, 'UTC') COMMENT 'Milliseconds since the unix epoch'
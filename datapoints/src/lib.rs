@@ -40,8 +40,17 @@ pub struct TwistDatapoint {
 pub struct GameSolveDatapoint {
     // Unique identifier of this game
     pub game_id: String,
-    // How long the solve took, in milliseconds
-    pub play_time_milliseconds: u32,
+    // How long the solve took, in milliseconds, with the penalty added, or None for a DNF
+    pub play_time_milliseconds: Option<u32>,
+    // How long the solve took from the first twist, in milliseconds, without the penalty added
+    pub raw_play_time_milliseconds: u32,
+    // WCA inspection penalty: OK, +2 for starting after 15 seconds, or DNF for starting after 17 seconds
+    pub penalty: String,
+    // How far into the solve each CFOP stage was first done, in milliseconds (a skipped stage gets the next one's time)
+    pub cross_milliseconds: Option<u32>,
+    pub f2l_milliseconds: Option<u32>,
+    pub oll_milliseconds: Option<u32>,
+    pub pll_milliseconds: Option<u32>,
     // Whether the cube recognised this as a new top score
     pub new_top_score: bool,
     // Solved cube face positions
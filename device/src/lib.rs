@@ -1,6 +1,6 @@
 #![no_std]
 
-use cube_model::{Cube, OutputMap5Faces, Output, Colors, Twist};
+use cube_model::{Cube, OutputMap5Faces, Output, Colors, Twist, Notation};
 use core::panic::PanicInfo;
 use core::slice;
 use core::str;
@@ -65,7 +65,7 @@ pub extern "C" fn update_from_string(cube: *mut Cube, s: *const u8) {
 pub extern "C" fn twist_cube(cube: *mut Cube, s: *const u8, l: u32, f0: *mut Cube, f1: *mut Cube, f2: *mut Cube){
     unsafe{
         let t = &str::from_utf8(slice::from_raw_parts(s, l as usize)).unwrap();
-        if let Ok(t) = Twist::from_string(t){
+        if let Ok(t) = Twist::from_string_in(t, Notation::Legacy){
             let frames =(*cube).twist(t);
             *f0 = frames[0];
             *f1 = frames[1];
@@ -74,6 +74,54 @@ pub extern "C" fn twist_cube(cube: *mut Cube, s: *const u8, l: u32, f0: *mut Cub
     }
 }
 
+// Like twist_cube, but fills `count` frames starting at `frames`, so the animation can be as smooth as the frame rate allows
+#[no_mangle]
+pub extern "C" fn twist_cube_frames(cube: *mut Cube, s: *const u8, l: u32, frames: *mut Cube, count: u32){
+    unsafe{
+        let t = &str::from_utf8(slice::from_raw_parts(s, l as usize)).unwrap();
+        if let Ok(t) = Twist::from_string_in(t, Notation::Legacy){
+            (*cube).twist_frames(t, slice::from_raw_parts_mut(frames, count as usize));
+        }
+    }
+}
+
+// Checks a whole algorithm sent in one go, twists in standard notation separated by spaces.
+// Gives how many twists it has, or -1 if any of them are bad
+#[no_mangle]
+pub extern "C" fn count_twists(s: *const u8, l: u32) -> i32{
+    unsafe{
+        match str::from_utf8(slice::from_raw_parts(s, l as usize)) {
+            Err(_) => -1
+            ,Ok(seq) => {
+                let mut count = 0;
+                for t in Twist::iter_from_string(seq){
+                    if t.is_err() {
+                        return -1;
+                    }
+                    count += 1;
+                }
+                count
+            }
+        }
+    }
+}
+
+// Does twist number `index` of an algorithm checked with count_twists, with animation frames like twist_cube_frames.
+// Gives 1 if it did a twist, or 0 if there are no more
+#[no_mangle]
+pub extern "C" fn twist_cube_sequence(cube: *mut Cube, s: *const u8, l: u32, index: u32, frames: *mut Cube, count: u32) -> i32{
+    unsafe{
+        let seq = str::from_utf8(slice::from_raw_parts(s, l as usize)).unwrap_or("");
+        match Twist::iter_from_string(seq).nth(index as usize) {
+            Some(Ok(t)) => {
+                (*cube).twist_frames(t, slice::from_raw_parts_mut(frames, count as usize));
+                1
+            }
+            ,_ => 0
+        }
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn is_solved(cube: *mut Cube) -> i32{
     unsafe {
@@ -15,7 +15,9 @@ const uint LED_STRIP_PIN = 7;
 const uint LED_STRIP_LEN = 45+1; // five faces of 9 LEDs, plus the initial skipped LED
 
 uint8_t thecube[CUBE_STRUCT_BYTES];
-uint8_t frames[3][CUBE_STRUCT_BYTES];
+#define NUM_FRAMES (12)
+#define ANIMATION_US (150000)
+uint8_t frames[NUM_FRAMES][CUBE_STRUCT_BYTES];
 uint8_t mapping[OUTPUT_ARRAY_BYTES];
 uint32_t led_data[45];
 
@@ -27,6 +29,7 @@ uint32_t led_data[45];
 #define MODE_LEDMAP_READ (3)
 #define MODE_SWITCHMAP_READ (4)
 #define MODE_BRIGHTNESS (5)
+#define MODE_SEQUENCE_READ (6)
 
 #define NUM_SUBFACES (6*9)
 #define NUM_SWITCH_INPUTS (18)
@@ -49,17 +52,39 @@ const int switch_inputs[NUM_SWITCH_INPUTS] = {
 #endif
 volatile char update_buffer[BUFLEN]; // big enough for two chars per subface, or for all the inputs
 
+// A whole algorithm sent in one command, played one twist at a time
+#define SEQUENCE_BUFLEN (256)
+char sequence_buffer[SEQUENCE_BUFLEN];
+int sequence_len = 0;
+int sequence_count = 0;
+int sequence_pos = -1; // -1 when no sequence is playing
+
 int cur_frame = 0;
 absolute_time_t frame_time;
 
+void play_sequence(){
+    // Start the next twist once the last one has finished animating
+    if (sequence_pos < 0 || cur_frame < NUM_FRAMES){
+        return;
+    }
+    if (twist_cube_sequence(thecube, (uint8_t*)sequence_buffer, sequence_len, sequence_pos, frames[0], NUM_FRAMES)){
+        cur_frame = 0;
+        frame_time = get_absolute_time();
+        sequence_pos += 1;
+    }
+    if (sequence_pos >= sequence_count){
+        sequence_pos = -1;
+    }
+}
+
 void update_leds(PicoLed::PicoLedController ledStrip){
     absolute_time_t now = get_absolute_time();
-    if (absolute_time_diff_us(frame_time, now) > 50000){
+    if (absolute_time_diff_us(frame_time, now) > ANIMATION_US / NUM_FRAMES){
         cur_frame += 1;
-        if (cur_frame > 3) {cur_frame = 3;}
+        if (cur_frame > NUM_FRAMES) {cur_frame = NUM_FRAMES;}
         frame_time = now;
     }
-    uint8_t *frame = cur_frame >= 3? thecube : frames[cur_frame];
+    uint8_t *frame = cur_frame >= NUM_FRAMES? thecube : frames[cur_frame];
     get_data(frame, mapping, led_data);
     //printf("?%d %d %d %d\n;", led_data[0], led_data[1], led_data[2], led_data[3]);
     for (int i = NUM_SKIP; i <= 45; i++){
@@ -137,7 +162,7 @@ void do_twist(int gpio){
     const char* twist = switch_map[gpio];
     if (mode == MODE_PLAY) {
         printf("*%s;\n", twist);
-        twist_cube(thecube, (uint8_t*)twist, 2, frames[0], frames[1], frames[2]);
+        twist_cube_frames(thecube, (uint8_t*)twist, 2, frames[0], NUM_FRAMES);
         cur_frame = 0;
         if (is_solved(thecube)) {
             printf("#\n");
@@ -226,7 +251,26 @@ int main(){
         int ic = getchar_timeout_us(0);
         if (ic != PICO_ERROR_TIMEOUT){
             char c = (char) ic & 0xff;
-            if (mode == MODE_BRIGHTNESS) {
+            // Twists use letters that are also commands, so this comes first
+            if (mode == MODE_SEQUENCE_READ) {
+                if (c == ';'){
+                    mode = next_mode;
+                    int count = sequence_len > SEQUENCE_BUFLEN ? -1 : count_twists((uint8_t*)sequence_buffer, sequence_len);
+                    if (count < 0) { printf("?badsequence\n;"); }
+                    else {
+                        sequence_count = count;
+                        sequence_pos = 0;
+                    }
+                }
+                else if (sequence_len < SEQUENCE_BUFLEN) {
+                    sequence_buffer[sequence_len++] = c;
+                }
+                else {
+                    // Too long, ignore the rest of it
+                    sequence_len = SEQUENCE_BUFLEN + 1;
+                }
+            }
+            else if (mode == MODE_BRIGHTNESS) {
                 brightness = c;
                 ledStrip.setBrightness(brightness);
                 mode = next_mode;
@@ -256,6 +300,12 @@ int main(){
                 next_mode = mode;
                 mode = MODE_BRIGHTNESS;
             }
+            else if (c == 's'){ // Sequence of twists, up to a ;
+                next_mode = mode;
+                mode = MODE_SEQUENCE_READ;
+                sequence_pos = -1;
+                sequence_len = 0;
+            }
             else {
                 if (mode == MODE_UPDATE_READ){
                     if ((update_pos) >= BUFLEN) { printf("?badstateupdate\n;"); }
@@ -310,6 +360,7 @@ int main(){
             // do nothing
         }
         check_twists();
+        play_sequence();
         update_leds(ledStrip);
     }
 }
@@ -1,17 +1,149 @@
 use std::time::{Instant, Duration};
 use std::fmt::{self,Display};
+use std::str::FromStr;
 use std::cmp::min;
+use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
-#[derive(Default, Debug)]
-pub struct TimerState{
-    game_id: Option<Uuid>
+pub mod stats;
+
+// Where the timer gets the time from, so that tests and replays from logs don't have to wait for real time to pass
+pub trait Clock{
+    fn now(&self) -> Instant;
+}
+
+#[derive(Default, Debug, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock{
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+// A clock that only moves when told to. Clones share the same time, so one can be kept to move the time for a timer
+// that owns another
+#[derive(Debug, Clone)]
+pub struct ManualClock{
+    now: Arc<Mutex<Instant>>
+}
+
+impl Default for ManualClock{
+    fn default() -> Self {
+        ManualClock{now: Arc::new(Mutex::new(Instant::now()))}
+    }
+}
+
+impl ManualClock{
+    pub fn advance(&self, d: Duration) {
+        *self.now.lock().unwrap() += d;
+    }
+}
+
+impl Clock for ManualClock{
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap()
+    }
+}
+
+// WCA rules: 15 seconds of inspection, starting up to 2 seconds after that is a two second penalty, any later and the
+// solve doesn't count. Other lengths of inspection get the same 2 seconds, and a game with no inspection starts straight away
+pub const WCA_INSPECTION: Duration = Duration::from_secs(15);
+// The longest inspection that a game can be started with
+pub const MAX_INSPECTION: Duration = Duration::from_secs(60);
+const LATE_START_ALLOWANCE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Penalty{
+    Ok
+    ,PlusTwo
+    ,Dnf
+}
+
+impl Penalty{
+    fn for_inspection(d: Duration, inspection: Duration) -> Penalty {
+        if d <= inspection {
+            Penalty::Ok
+        }
+        else if d <= inspection.saturating_add(LATE_START_ALLOWANCE) {
+            Penalty::PlusTwo
+        }
+        else {
+            Penalty::Dnf
+        }
+    }
+
+    // How much time the penalty adds, a DNF has no time to add to
+    pub fn added_time(&self) -> Option<Duration> {
+        match self {
+            Penalty::Ok => Some(Duration::new(0,0))
+            ,Penalty::PlusTwo => Some(Duration::from_secs(2))
+            ,Penalty::Dnf => None
+        }
+    }
+}
+
+impl Display for Penalty{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            Penalty::Ok => "OK"
+            ,Penalty::PlusTwo => "+2"
+            ,Penalty::Dnf => "DNF"
+        })
+    }
+}
+
+impl FromStr for Penalty{
+    type Err = ();
+    fn from_str(s: &str) -> Result<Penalty, ()> {
+        match s {
+            "OK" => Ok(Penalty::Ok)
+            ,"+2" => Ok(Penalty::PlusTwo)
+            ,"DNF" => Ok(Penalty::Dnf)
+            ,_ => Err(())
+        }
+    }
+}
+
+// The stages of a CFOP solve, in the order they get done
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage{
+    Cross
+    ,F2l
+    ,Oll
+    ,Pll
+}
+
+impl Stage{
+    pub const ALL: [Stage; 4] = [Stage::Cross, Stage::F2l, Stage::Oll, Stage::Pll];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Cross => "Cross"
+            ,Stage::F2l => "F2L"
+            ,Stage::Oll => "OLL"
+            ,Stage::Pll => "PLL"
+        }
+    }
+}
+
+// The timer used everywhere outside of tests
+pub type TimerState = Timer<SystemClock>;
+
+#[derive(Debug)]
+pub struct Timer<C: Clock>{
+    clock: C
+    // How long this game's inspection is, zero for no inspection
+    ,inspection: Duration
+    ,game_id: Option<Uuid>
     ,started: Option<Instant>
     ,inspection_end: Option<Instant>
     ,ended: Option<Instant>
+    // When each stage was first done, in the same order as Stage::ALL
+    ,splits: [Option<Instant>; 4]
 }
 
-impl Display for TimerState{
+impl<C: Clock> Display for Timer<C>{
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match self.started{
             Some(_s) => "t0"
@@ -29,16 +161,36 @@ impl Display for TimerState{
     }
 }
 
+impl<C: Clock + Default> Default for Timer<C>{
+    fn default() -> Self {
+        Timer::with_clock(C::default())
+    }
+}
+
 fn round_ms(d: Duration) -> Duration {
     Duration::from_millis(d.as_millis().try_into().unwrap_or(0))
 }
 
-impl TimerState{
+impl<C: Clock> Timer<C>{
+    pub fn with_clock(clock: C) -> Self {
+        Timer{clock, inspection: WCA_INSPECTION, game_id: None, started: None, inspection_end: None, ended: None, splits: [None; 4]}
+    }
+
+    pub fn inspection_time(&self) -> Duration {
+        self.inspection
+    }
+
+    // Once inspection has gone on this long, the solve starts whether or not the cube has been twisted
+    fn late_start(&self) -> Duration {
+        self.inspection.saturating_add(LATE_START_ALLOWANCE)
+    }
+
     pub fn reset(&mut self) {
         self.game_id = None;
         self.started = None;
         self.inspection_end = None;
         self.ended = None;
+        self.splits = [None; 4];
     }
 
     pub fn game_id(&self) -> Option<Uuid> {
@@ -48,7 +200,7 @@ impl TimerState{
     pub fn is_inspecting(&self, t: Option<Instant>) -> bool {
         match t {
             None => {self.started.is_some() && self.inspection_end.is_none()}
-            Some(t) => {self.started.is_some() && self.inspection_end.is_none() && t - self.started.unwrap() < Duration::new(15,0)}
+            Some(t) => {self.started.is_some() && self.inspection_end.is_none() && t - self.started.unwrap() <= self.late_start()}
         }
     }
 
@@ -64,11 +216,11 @@ impl TimerState{
         self.is_ended() || !self.is_started()
     }
 
-    pub fn recorded_time(&self) -> Option<Duration>{
+    // How long the solve took from the first twist, without any penalty
+    pub fn solve_time(&self) -> Option<Duration>{
         match (self.started, self.inspection_end, self.ended) {
             (Some(start), Some(inspect_end), Some(end)) => {
-                const FIFTEEN: Duration = Duration::from_secs(15);
-                Some((end - start)- min(inspect_end - start, FIFTEEN))
+                Some((end - start) - min(inspect_end - start, self.late_start()))
             }
             ,_=>{
                 None
@@ -76,9 +228,24 @@ impl TimerState{
         }
     }
 
+    // The solve time with the penalty added, or None for a DNF
+    pub fn recorded_time(&self) -> Option<Duration>{
+        self.solve_time().and_then(|t| Some(t + self.penalty()?.added_time()?))
+    }
+
+    // Known once inspection is over, either from the first twist or from running out of time
+    pub fn penalty(&self) -> Option<Penalty>{
+        let start = self.started?;
+        match self.inspection_end {
+            Some(end) => Some(Penalty::for_inspection(end - start, self.inspection))
+            ,None if !self.is_inspecting(Some(self.clock.now())) => Some(Penalty::Dnf)
+            ,None => None
+        }
+    }
+
     pub fn twist(&mut self) -> bool {
         if self.is_inspecting(None){
-            self.inspection_end = Some(Instant::now());
+            self.inspection_end = Some(self.clock.now());
             true
         }
         else {
@@ -87,11 +254,19 @@ impl TimerState{
     }
 
     pub fn start(&mut self) -> bool {
+        self.start_with_inspection(WCA_INSPECTION)
+    }
+
+    // Starts a game with a different length of inspection, with no inspection the first twist isn't waited for
+    pub fn start_with_inspection(&mut self, inspection: Duration) -> bool {
         if self.can_start() {
-            self.started = Some(Instant::now());
+            let now = self.clock.now();
+            self.inspection = inspection;
+            self.started = Some(now);
             self.game_id = Some(Uuid::new_v4());
-            self.inspection_end = None;
+            self.inspection_end = if inspection.is_zero() {Some(now)} else {None};
             self.ended = None;
+            self.splits = [None; 4];
             true
         }
         else {
@@ -108,18 +283,73 @@ impl TimerState{
                 false
             }
             else {
-                self.ended = Some(Instant::now());
+                let now = self.clock.now();
+                // Solving the cube finishes every stage that hadn't already been seen
+                self.record_stage(Stage::Pll, now);
+                self.ended = Some(now);
                 true
             }
         }
     }
 
+    // Records the time that a stage was done, and any earlier ones that were skipped. Only the first time counts,
+    // and only during a solve. Gives true if anything new was recorded
+    pub fn stage_done(&mut self, stage: Stage) -> bool {
+        let now = self.clock.now();
+        self.record_stage(stage, now)
+    }
+
+    fn record_stage(&mut self, stage: Stage, at: Instant) -> bool {
+        if self.inspection_end.is_none() || self.ended.is_some() {
+            return false;
+        }
+        let mut recorded = false;
+        for (s, split) in Stage::ALL.iter().zip(self.splits.iter_mut()){
+            if split.is_none() {
+                *split = Some(at);
+                recorded = true;
+            }
+            if *s == stage {
+                break;
+            }
+        }
+        recorded
+    }
+
+    // How long into the solve each stage was done, in the same order as Stage::ALL
+    pub fn splits(&self) -> [Option<Duration>; 4] {
+        let mut splits = [None; 4];
+        if let Some(solve_start) = self.effective_inspection_end() {
+            for (d, split) in splits.iter_mut().zip(self.splits){
+                *d = split.map(|t| round_ms(t.saturating_duration_since(solve_start)));
+            }
+        }
+        splits
+    }
+
+    // Split times in milliseconds, X for stages not done yet
+    pub fn serialise_splits(&self) -> (String, String, String, String){
+        let s = self.splits().map(|d| d.map(|d| d.as_millis().to_string()).unwrap_or("X".to_string()));
+        let [cross, f2l, oll, pll] = s;
+        (cross, f2l, oll, pll)
+    }
+
+    pub fn deserialise_splits(splits: (String, String, String, String)) -> Result<[Option<Duration>; 4], ()>{
+        let (cross, f2l, oll, pll) = splits;
+        Ok([
+            Self::str_to_opt_dir(cross)?
+            ,Self::str_to_opt_dir(f2l)?
+            ,Self::str_to_opt_dir(oll)?
+            ,Self::str_to_opt_dir(pll)?
+        ])
+    }
+
     pub fn duration_so_far(&self) -> Duration {
         if !self.is_started(){
             Duration::new(0,0)
         }
         else{
-            Instant::now() - self.started.unwrap()
+            self.clock.now() - self.started.unwrap()
         }
     }
 
@@ -129,16 +359,20 @@ impl TimerState{
         }
         else{
             if self.is_inspecting(at){
-                let t = Instant::now() - self.started.unwrap();
-                if t > Duration::new(15,0){
-                    Duration::new(15,0)
+                let t = self.clock.now() - self.started.unwrap();
+                if t > self.late_start(){
+                    self.late_start()
                 }
                 else{
                     round_ms(t)
                 }
             }
             else{
-                round_ms(self.inspection_end.unwrap() - self.started.unwrap())
+                match self.inspection_end {
+                    Some(end) => {round_ms(end - self.started.unwrap())}
+                    // Ran out of time before the first twist
+                    ,None => {self.late_start()}
+                }
             }
         }
     }
@@ -146,8 +380,8 @@ impl TimerState{
     pub fn effective_inspection_end(&self) -> Option<Instant>{
         self.inspection_end.and_then(|t|{
             let s = self.started.unwrap();
-            if t-s > Duration::new(15,0) {
-                Some(s + Duration::new(15,0))
+            if t-s > self.late_start() {
+                Some(s + self.late_start())
             }
             else{
                 Some(t)
@@ -156,7 +390,7 @@ impl TimerState{
     }
 
     pub fn solve_so_far(&self) -> Duration {
-        let at = Instant::now();
+        let at = self.clock.now();
         if self.is_ended() {
             round_ms(self.ended.unwrap() - self.effective_inspection_end().unwrap())
         }
@@ -169,17 +403,25 @@ impl TimerState{
             }
             else{
                 match self.effective_inspection_end() {
-                    Some(e) => {Instant::now() - e}
+                    Some(e) => {at - e}
                     ,None => {
                         let start = self.started.unwrap();
-                        round_ms(at - start - Duration::new(15,0))
+                        round_ms(at - start - self.late_start())
                     }
                 }
             }
         }
     }
 
-    pub fn serialise(&self) -> (String, String, String){
+    // Start, inspection end and solve end times in milliseconds, the penalty, X for anything not known yet,
+    // and the length of inspection in milliseconds
+    pub fn serialise(&self) -> (String, String, String, String, String){
+        let (s, i, e) = self.serialise_times();
+        let p = self.penalty().map(|p| p.to_string()).unwrap_or("X".to_string());
+        (s, i, e, p, self.inspection.as_millis().to_string())
+    }
+
+    fn serialise_times(&self) -> (String, String, String){
         match self.started{
             None => {("X".to_string(), "X".to_string(), "X".to_string())}
             Some(start) => {
@@ -210,29 +452,52 @@ impl TimerState{
         }
     }
 
-    fn deserialise_raw(start: Instant, durs: (Option<Duration>, Option<Duration>, Option<Duration>)) -> Result<Self, ()>{
+    fn deserialise_raw(clock: C, start: Instant, inspection: String, durs: (Option<Duration>, Option<Duration>, Option<Duration>)) -> Result<Self, ()>{
         let (s, i, e) = durs;
         let add_start = |d|Some(start+d);
-        Ok(TimerState{
-            game_id: None
+        Ok(Timer{
+            clock
+            ,inspection: Self::str_to_opt_dir(inspection)?.ok_or(())?
+            ,game_id: None
             ,started: s.and_then(add_start)
             ,inspection_end: i.and_then(add_start)
             ,ended: e.and_then(add_start)
+            ,splits: [None; 4]
         })
     }
 
-    pub fn deserialise(start: Instant, durs: (String, String, String)) -> Result<Self, ()>{
-        let (s, i, e) = durs;
-        Self::deserialise_raw(start, (
+    // The penalty follows from the times, it is sent so that nothing else needs to know the rules, but it must still be valid
+    fn check_penalty(p: &str) -> Result<(), ()>{
+        if p == "X" {
+            Ok(())
+        }
+        else{
+            Penalty::from_str(p).map(|_| ())
+        }
+    }
+
+    pub fn deserialise(start: Instant, durs: (String, String, String, String, String)) -> Result<Self, ()> where C: Default{
+        Self::deserialise_with_clock(C::default(), start, durs)
+    }
+
+    pub fn deserialise_with_clock(clock: C, start: Instant, durs: (String, String, String, String, String)) -> Result<Self, ()>{
+        let (s, i, e, p, inspection) = durs;
+        Self::check_penalty(&p)?;
+        Self::deserialise_raw(clock, start, inspection, (
             Self::str_to_opt_dir(s)?
             ,Self::str_to_opt_dir(i)?
             ,Self::str_to_opt_dir(e)?
         ))
     }
 
-    pub fn deserialise_now_ish(durs: (String, String, String)) -> Result<Self, ()>{
-        let t = Instant::now();
-        let (s, i, e) = durs;
+    pub fn deserialise_now_ish(durs: (String, String, String, String, String)) -> Result<Self, ()> where C: Default{
+        Self::deserialise_now_ish_with_clock(C::default(), durs)
+    }
+
+    pub fn deserialise_now_ish_with_clock(clock: C, durs: (String, String, String, String, String)) -> Result<Self, ()>{
+        let t = clock.now();
+        let (s, i, e, p, inspection) = durs;
+        Self::check_penalty(&p)?;
         let s = Self::str_to_opt_dir(s)?;
         let i = Self::str_to_opt_dir(i)?;
         let e = Self::str_to_opt_dir(e)?;
@@ -240,22 +505,24 @@ impl TimerState{
         if let Some(t) = s {if t > max {max = t;}}
         if let Some(t) = i {if t > max {max = t;}}
         if let Some(t) = e {if t > max {max = t;}}
-        Self::deserialise_raw(t - max, (s,i,e))
+        Self::deserialise_raw(clock, t - max, inspection, (s,i,e))
     }
 }
 
 
 #[cfg(test)]
 mod tests {
-    use crate::TimerState;
-    use std::time::{Instant};
+    use crate::{TimerState, Timer, ManualClock, Clock, Penalty, Stage};
+    use std::time::{Instant, Duration};
     #[test]
     fn basic_tests() {
         let mut state = TimerState::default();
-        let (start, iend, end) = state.serialise();
+        let (start, iend, end, penalty, inspection) = state.serialise();
         assert!(&start == "X");
         assert!(&iend == "X");
         assert!(&end == "X");
+        assert!(&penalty == "X");
+        assert!(&inspection == "15000");
         assert!(!state.is_started());
         assert!(!state.is_inspecting(None));
         assert!(!state.is_ended());
@@ -294,10 +561,12 @@ mod tests {
         assert!(!state.is_inspecting(None));
         assert!(state.is_ended());
         assert_eq!(state.game_id().unwrap(), first_game_id);
-        let (start, iend, end) = state.serialise();
+        let (start, iend, end, penalty, inspection) = state.serialise();
         assert!(&start != "X");
         assert!(&iend != "X");
         assert!(&end != "X");
+        assert!(&penalty == "OK");
+        assert!(&inspection == "15000");
 
         // Resetting results in no game id
         state.reset();
@@ -314,11 +583,195 @@ mod tests {
     #[test]
     fn ser_deser() {
         let n =  Instant::now();
-        let blank_state = TimerState::deserialise(n, ("X".to_string(), "X".to_string(), "X".to_string())).unwrap();
-        let started_state = TimerState::deserialise(n, ("0".to_string(), "X".to_string(), "X".to_string())).unwrap();
-        let ended_state = TimerState::deserialise(n, ("0".to_string(), "15000".to_string(), "60000".to_string())).unwrap();
+        let blank_state = TimerState::deserialise(n, ("X".to_string(), "X".to_string(), "X".to_string(), "X".to_string(), "15000".to_string())).unwrap();
+        let started_state = TimerState::deserialise(n, ("0".to_string(), "X".to_string(), "X".to_string(), "X".to_string(), "15000".to_string())).unwrap();
+        let ended_state = TimerState::deserialise(n, ("0".to_string(), "15000".to_string(), "60000".to_string(), "OK".to_string(), "15000".to_string())).unwrap();
+        let late_state = TimerState::deserialise(n, ("0".to_string(), "16000".to_string(), "60000".to_string(), "+2".to_string(), "15000".to_string())).unwrap();
         assert_eq!(blank_state.serialise(), TimerState::default().serialise());
-        assert_eq!(started_state.serialise(), ("0".to_string(), "X".to_string(), "X".to_string()));
-        assert_eq!(ended_state.serialise(), ("0".to_string(), "15000".to_string(), "60000".to_string()));
+        assert_eq!(started_state.serialise(), ("0".to_string(), "X".to_string(), "X".to_string(), "X".to_string(), "15000".to_string()));
+        assert_eq!(ended_state.serialise(), ("0".to_string(), "15000".to_string(), "60000".to_string(), "OK".to_string(), "15000".to_string()));
+        assert_eq!(late_state.serialise(), ("0".to_string(), "16000".to_string(), "60000".to_string(), "+2".to_string(), "15000".to_string()));
+        assert!(TimerState::deserialise(n, ("0".to_string(), "X".to_string(), "X".to_string(), "+3".to_string(), "15000".to_string())).is_err());
+    }
+
+    #[test]
+    fn timed_solve() {
+        let clock = ManualClock::default();
+        let mut state = Timer::with_clock(clock.clone());
+        assert!(state.start());
+        clock.advance(Duration::from_millis(3200));
+        assert!(state.is_inspecting(Some(clock.now())));
+        assert_eq!(state.inspection_so_far(None), Duration::from_millis(3200));
+        assert_eq!(state.solve_so_far(), Duration::new(0,0));
+        assert!(state.twist());
+        // Times are only rounded to the millisecond once the solve is over
+        clock.advance(Duration::from_micros(10_000_400));
+        assert_eq!(state.solve_so_far(), Duration::from_micros(10_000_400));
+        assert_eq!(state.duration_so_far(), Duration::from_micros(13_200_400));
+        assert!(state.solved());
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(state.recorded_time(), Some(Duration::from_micros(10_000_400)));
+        assert_eq!(state.solve_so_far(), Duration::from_millis(10_000));
+        assert_eq!(state.inspection_so_far(None), Duration::from_millis(3200));
+        assert_eq!(state.penalty(), Some(Penalty::Ok));
+        assert_eq!(state.serialise(), ("0".to_string(), "3200".to_string(), "13200".to_string(), "OK".to_string(), "15000".to_string()));
+        assert_eq!(state.to_string(), "(s:t0, i:3.2s, e:13.2004s)");
+    }
+
+    #[test]
+    fn late_start() {
+        let clock = ManualClock::default();
+        let mut state = Timer::with_clock(clock.clone());
+        assert!(state.start());
+        clock.advance(Duration::from_millis(14_999));
+        assert!(state.is_inspecting(Some(clock.now())));
+        assert_eq!(state.inspection_so_far(Some(clock.now())), Duration::from_millis(14_999));
+        assert_eq!(state.penalty(), None);
+        // Between 15 and 17 seconds it is still possible to start, with two seconds added
+        clock.advance(Duration::from_millis(1001));
+        assert!(state.is_inspecting(Some(clock.now())));
+        assert_eq!(state.inspection_so_far(Some(clock.now())), Duration::from_secs(16));
+        assert_eq!(state.solve_so_far(), Duration::new(0,0));
+        assert_eq!(state.penalty(), None);
+        assert!(state.twist());
+        assert_eq!(state.penalty(), Some(Penalty::PlusTwo));
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(state.solve_so_far(), Duration::from_secs(5));
+        assert!(state.solved());
+        assert_eq!(state.solve_time(), Some(Duration::from_secs(5)));
+        assert_eq!(state.recorded_time(), Some(Duration::from_secs(7)));
+        assert_eq!(state.serialise(), ("0".to_string(), "16000".to_string(), "21000".to_string(), "+2".to_string(), "15000".to_string()));
+    }
+
+    #[test]
+    fn did_not_finish() {
+        let clock = ManualClock::default();
+        let mut state = Timer::with_clock(clock.clone());
+        assert!(state.start());
+        clock.advance(Duration::from_secs(17));
+        assert!(state.is_inspecting(Some(clock.now())));
+        assert_eq!(state.penalty(), None);
+        // After 17 seconds the solve clock starts, but the solve can't count
+        clock.advance(Duration::from_millis(1));
+        assert!(!state.is_inspecting(Some(clock.now())));
+        assert!(state.is_inspecting(None));
+        assert_eq!(state.penalty(), Some(Penalty::Dnf));
+        assert_eq!(state.inspection_so_far(Some(clock.now())), Duration::from_secs(17));
+        assert_eq!(state.inspection_so_far(None), Duration::from_secs(17));
+        assert_eq!(state.solve_so_far(), Duration::from_millis(1));
+        assert_eq!(state.serialise(), ("0".to_string(), "X".to_string(), "X".to_string(), "DNF".to_string(), "15000".to_string()));
+        clock.advance(Duration::from_millis(999));
+        assert!(state.twist());
+        assert_eq!(state.effective_inspection_end(), state.started.map(|s| s + Duration::from_secs(17)));
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(state.solve_so_far(), Duration::from_secs(6));
+        assert!(state.solved());
+        assert_eq!(state.solve_time(), Some(Duration::from_secs(6)));
+        assert_eq!(state.recorded_time(), None);
+        assert_eq!(state.serialise(), ("0".to_string(), "18000".to_string(), "23000".to_string(), "DNF".to_string(), "15000".to_string()));
+        assert_eq!(Penalty::Dnf.added_time(), None);
+        assert_eq!("+2".parse::<Penalty>(), Ok(Penalty::PlusTwo));
+    }
+
+    #[test]
+    fn inspection_lengths() {
+        let clock = ManualClock::default();
+        let mut state = Timer::with_clock(clock.clone());
+        // A shorter inspection moves the penalty times with it
+        assert!(state.start_with_inspection(Duration::from_secs(8)));
+        assert_eq!(state.inspection_time(), Duration::from_secs(8));
+        clock.advance(Duration::from_millis(9500));
+        assert!(state.is_inspecting(Some(clock.now())));
+        assert!(state.twist());
+        assert_eq!(state.penalty(), Some(Penalty::PlusTwo));
+        clock.advance(Duration::from_secs(10));
+        assert!(state.solved());
+        assert_eq!(state.recorded_time(), Some(Duration::from_secs(12)));
+        assert_eq!(state.serialise(), ("0".to_string(), "9500".to_string(), "19500".to_string(), "+2".to_string(), "8000".to_string()));
+        let copy = Timer::deserialise_now_ish_with_clock(clock.clone(), state.serialise()).unwrap();
+        assert_eq!(copy.inspection_time(), Duration::from_secs(8));
+        assert_eq!(copy.recorded_time(), Some(Duration::from_secs(12)));
+
+        // With no inspection the solve is timed from the start, and the first twist isn't special
+        state.reset();
+        assert!(state.start_with_inspection(Duration::new(0,0)));
+        assert!(!state.is_inspecting(None));
+        assert!(!state.is_inspecting(Some(clock.now())));
+        assert_eq!(state.penalty(), Some(Penalty::Ok));
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(state.solve_so_far(), Duration::from_secs(30));
+        assert!(!state.twist());
+        clock.advance(Duration::from_secs(10));
+        assert!(state.solved());
+        assert_eq!(state.recorded_time(), Some(Duration::from_secs(40)));
+        assert_eq!(state.serialise(), ("0".to_string(), "0".to_string(), "40000".to_string(), "OK".to_string(), "0".to_string()));
+
+        // Each game gets its own setting, a plain start is back to WCA inspection
+        state.reset();
+        assert!(state.start());
+        assert_eq!(state.inspection_time(), Duration::from_secs(15));
+        assert!(state.is_inspecting(None));
+
+        // Even a silly length doesn't overflow
+        state.reset();
+        assert!(state.start_with_inspection(Duration::MAX));
+        clock.advance(Duration::from_secs(100));
+        assert!(state.is_inspecting(Some(clock.now())));
+        assert_eq!(state.penalty(), None);
+        assert!(state.twist());
+        assert_eq!(state.penalty(), Some(Penalty::Ok));
+    }
+
+    #[test]
+    fn splits() {
+        let clock = ManualClock::default();
+        let mut state = Timer::with_clock(clock.clone());
+        // Nothing counts before the game or during inspection
+        assert!(!state.stage_done(Stage::Cross));
+        assert!(state.start());
+        clock.advance(Duration::from_secs(5));
+        assert!(!state.stage_done(Stage::Cross));
+        assert!(state.twist());
+        clock.advance(Duration::from_millis(4200));
+        assert!(state.stage_done(Stage::Cross));
+        // Only the first time a stage is done counts
+        clock.advance(Duration::from_secs(1));
+        assert!(!state.stage_done(Stage::Cross));
+        assert_eq!(state.splits(), [Some(Duration::from_millis(4200)), None, None, None]);
+        // Skipping a stage gives it the same time as the next one
+        clock.advance(Duration::from_secs(20));
+        assert!(state.stage_done(Stage::Oll));
+        assert_eq!(state.serialise_splits(), ("4200".to_string(), "25200".to_string(), "25200".to_string(), "X".to_string()));
+        clock.advance(Duration::from_millis(6300));
+        assert!(state.solved());
+        assert!(!state.stage_done(Stage::Pll));
+        let splits = [Some(Duration::from_millis(4200)), Some(Duration::from_millis(25200)), Some(Duration::from_millis(25200)), Some(Duration::from_millis(31500))];
+        assert_eq!(state.splits(), splits);
+        assert_eq!(TimerState::deserialise_splits(state.serialise_splits()), Ok(splits));
+        assert!(TimerState::deserialise_splits(("1".to_string(), "X".to_string(), "?".to_string(), "X".to_string())).is_err());
+        assert_eq!(Stage::ALL.map(|s| s.name()), ["Cross", "F2L", "OLL", "PLL"]);
+
+        // A new game starts with no splits
+        state.reset();
+        assert_eq!(state.splits(), [None; 4]);
+        assert!(state.start_with_inspection(Duration::new(0,0)));
+        clock.advance(Duration::from_secs(3));
+        assert!(state.stage_done(Stage::Cross));
+        assert_eq!(state.splits(), [Some(Duration::from_secs(3)), None, None, None]);
+    }
+
+    #[test]
+    fn deserialise_with_clock() {
+        let clock = ManualClock::default();
+        // A solve that had just finished 4 seconds of inspection when it was sent
+        let mut state = Timer::deserialise_now_ish_with_clock(clock.clone(), ("0".to_string(), "4000".to_string(), "X".to_string(), "OK".to_string(), "15000".to_string())).unwrap();
+        assert_eq!(state.duration_so_far(), Duration::from_secs(4));
+        clock.advance(Duration::from_millis(16_000));
+        assert_eq!(state.solve_so_far(), Duration::from_secs(16));
+        assert!(state.solved());
+        assert_eq!(state.recorded_time(), Some(Duration::from_secs(16)));
+        assert_eq!(state.serialise(), ("0".to_string(), "4000".to_string(), "20000".to_string(), "OK".to_string(), "15000".to_string()));
+        assert!(Timer::deserialise_with_clock(clock.clone(), Instant::now(), ("0".to_string(), "nope".to_string(), "X".to_string(), "X".to_string(), "15000".to_string())).is_err());
+        assert!(Timer::deserialise_now_ish_with_clock(clock, ("0".to_string(), "X".to_string(), "X".to_string(), "ok".to_string(), "15000".to_string())).is_err());
     }
 }
@@ -0,0 +1,292 @@
+// Statistics over a session of solves, following the WCA rules for averages
+//
+// A mean of n counts every solve, so any DNF makes it a DNF. An average of n drops the best and worst solves first,
+// so one DNF can be dropped as the worst, but two or more make the average a DNF
+
+use std::time::Duration;
+use std::fmt::{self,Display};
+use std::str::FromStr;
+
+// Times come before DNFs, so a DNF is the worst result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SolveTime{
+    Time(Duration)
+    ,Dnf
+}
+
+impl SolveTime{
+    // From a recorded time, which is None for a DNF
+    pub fn from_recorded(time: Option<Duration>) -> SolveTime {
+        match time {
+            Some(t) => SolveTime::Time(t)
+            ,None => SolveTime::Dnf
+        }
+    }
+}
+
+// Milliseconds or DNF
+impl Display for SolveTime{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveTime::Time(t) => write!(f, "{}", t.as_millis())
+            ,SolveTime::Dnf => write!(f, "DNF")
+        }
+    }
+}
+
+impl FromStr for SolveTime{
+    type Err = ();
+    fn from_str(s: &str) -> Result<SolveTime, ()> {
+        if s == "DNF" {
+            Ok(SolveTime::Dnf)
+        }
+        else {
+            s.parse().map(|ms| SolveTime::Time(Duration::from_millis(ms))).map_err(|_| ())
+        }
+    }
+}
+
+fn mean(times: &[SolveTime]) -> SolveTime {
+    let mut total = Duration::new(0,0);
+    for t in times{
+        match t {
+            SolveTime::Time(t) => {total += *t;}
+            ,SolveTime::Dnf => {return SolveTime::Dnf;}
+        }
+    }
+    let ms = total.as_millis() / times.len() as u128;
+    SolveTime::Time(Duration::from_millis(ms.try_into().unwrap_or(u64::MAX)))
+}
+
+// The most solves that any of the averages look at
+const KEPT_SOLVES: usize = 12;
+
+// Only the most recent solves are kept, the count, best and worst are kept up to date as solves are added
+#[derive(Debug, Default, Clone)]
+pub struct Session{
+    solves: Vec<SolveTime>
+    ,count: usize
+    ,best: Option<SolveTime>
+    ,worst: Option<SolveTime>
+}
+
+impl Session{
+    pub fn add(&mut self, time: SolveTime) {
+        if self.solves.len() == KEPT_SOLVES {
+            self.solves.remove(0);
+        }
+        self.solves.push(time);
+        self.count += 1;
+        self.best = Some(self.best.map_or(time, |t| t.min(time)));
+        self.worst = Some(self.worst.map_or(time, |t| t.max(time)));
+    }
+
+    pub fn reset(&mut self) {
+        *self = Session::default();
+    }
+
+    // The most recent solves, up to the last 12
+    pub fn solves(&self) -> &[SolveTime] {
+        &self.solves
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn best(&self) -> Option<SolveTime> {
+        self.best
+    }
+
+    pub fn worst(&self) -> Option<SolveTime> {
+        self.worst
+    }
+
+    fn last(&self, n: usize) -> Option<&[SolveTime]> {
+        if n == 0 || self.solves.len() < n {
+            None
+        }
+        else {
+            Some(&self.solves[self.solves.len() - n..])
+        }
+    }
+
+    // The mean of the last n solves, None until there have been that many, or if n is more than the 12 that are kept
+    pub fn mean_of(&self, n: usize) -> Option<SolveTime> {
+        self.last(n).map(mean)
+    }
+
+    // The average of the last n solves, without the best and worst 5% (at least one of each), None until there have
+    // been that many and at least three
+    pub fn average_of(&self, n: usize) -> Option<SolveTime> {
+        if n < 3 {
+            return None;
+        }
+        let mut times = self.last(n)?.to_vec();
+        times.sort();
+        let trim = n.div_ceil(20);
+        Some(mean(&times[trim..n - trim]))
+    }
+
+    pub fn summary(&self) -> Summary {
+        Summary{
+            count: self.count
+            ,best: self.best()
+            ,worst: self.worst()
+            ,mo3: self.mean_of(3)
+            ,ao5: self.average_of(5)
+            ,ao12: self.average_of(12)
+        }
+    }
+}
+
+// Everything that the controller shows about a session
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Summary{
+    pub count: usize
+    ,pub best: Option<SolveTime>
+    ,pub worst: Option<SolveTime>
+    ,pub mo3: Option<SolveTime>
+    ,pub ao5: Option<SolveTime>
+    ,pub ao12: Option<SolveTime>
+}
+
+fn opt_to_str(t: Option<SolveTime>) -> String {
+    t.map(|t| t.to_string()).unwrap_or("X".to_string())
+}
+
+fn str_to_opt(s: &str) -> Result<Option<SolveTime>, ()> {
+    if s == "X" {
+        Ok(None)
+    }
+    else {
+        SolveTime::from_str(s).map(Some)
+    }
+}
+
+impl Summary{
+    // Solve count, then best, worst, mo3, ao5 and ao12 as milliseconds, DNF, or X when there aren't enough solves
+    pub fn serialise(&self) -> Vec<String> {
+        vec![
+            self.count.to_string()
+            ,opt_to_str(self.best)
+            ,opt_to_str(self.worst)
+            ,opt_to_str(self.mo3)
+            ,opt_to_str(self.ao5)
+            ,opt_to_str(self.ao12)
+        ]
+    }
+
+    pub fn deserialise(args: &[String]) -> Result<Summary, ()> {
+        if args.len() < 6 {
+            return Err(());
+        }
+        Ok(Summary{
+            count: args[0].parse().map_err(|_| ())?
+            ,best: str_to_opt(&args[1])?
+            ,worst: str_to_opt(&args[2])?
+            ,mo3: str_to_opt(&args[3])?
+            ,ao5: str_to_opt(&args[4])?
+            ,ao12: str_to_opt(&args[5])?
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use super::{Session, SolveTime, Summary};
+
+    fn time(ms: u64) -> SolveTime {
+        SolveTime::Time(Duration::from_millis(ms))
+    }
+
+    #[test]
+    fn averages() {
+        let mut session = Session::default();
+        assert_eq!(session.best(), None);
+        assert_eq!(session.mean_of(3), None);
+        for ms in [12000, 10000, 14000]{
+            session.add(time(ms));
+        }
+        assert_eq!(session.mean_of(3), Some(time(12000)));
+        assert_eq!(session.average_of(5), None);
+        session.add(time(9000));
+        session.add(time(20000));
+        // Drops 9s and 20s
+        assert_eq!(session.average_of(5), Some(time(12000)));
+        assert_eq!(session.mean_of(3), Some(time(14333)));
+        assert_eq!(session.best(), Some(time(9000)));
+        assert_eq!(session.worst(), Some(time(20000)));
+
+        // One DNF is dropped as the worst of an average, but not from a mean
+        session.add(SolveTime::Dnf);
+        assert_eq!(session.average_of(5), Some(time(14666)));
+        assert_eq!(session.mean_of(3), Some(SolveTime::Dnf));
+        assert_eq!(session.worst(), Some(SolveTime::Dnf));
+        assert_eq!(session.best(), Some(time(9000)));
+        // Two DNFs can't both be dropped
+        session.add(SolveTime::from_recorded(None));
+        assert_eq!(session.average_of(5), Some(SolveTime::Dnf));
+        // Only the most recent solves count
+        for ms in [11000, 11000, 11000, 11000, 11000]{
+            session.add(SolveTime::from_recorded(Some(Duration::from_millis(ms))));
+        }
+        assert_eq!(session.mean_of(3), Some(time(11000)));
+        assert_eq!(session.average_of(5), Some(time(11000)));
+        // The two DNFs are still in the last twelve
+        assert_eq!(session.average_of(12), Some(SolveTime::Dnf));
+        session.reset();
+        assert!(session.solves().is_empty());
+    }
+
+    #[test]
+    fn average_of_12() {
+        let mut session = Session::default();
+        for ms in [10000, 11000, 12000, 13000, 14000, 15000, 16000, 17000, 18000, 19000, 20000]{
+            session.add(time(ms));
+        }
+        assert_eq!(session.average_of(12), None);
+        session.add(SolveTime::Dnf);
+        // Drops 10s and the DNF
+        assert_eq!(session.average_of(12), Some(time(15500)));
+        session.add(SolveTime::Dnf);
+        assert_eq!(session.average_of(12), Some(SolveTime::Dnf));
+    }
+
+    #[test]
+    fn long_sessions() {
+        let mut session = Session::default();
+        session.add(time(5000));
+        session.add(SolveTime::Dnf);
+        for _ in 0..20{
+            session.add(time(10000));
+        }
+        // The first solves are gone from the averages, but not from the count, best and worst
+        assert_eq!(session.solves().len(), 12);
+        assert_eq!(session.count(), 22);
+        assert_eq!(session.best(), Some(time(5000)));
+        assert_eq!(session.worst(), Some(SolveTime::Dnf));
+        assert_eq!(session.average_of(12), Some(time(10000)));
+        assert_eq!(session.average_of(13), None);
+        assert_eq!(session.summary().count, 22);
+        session.reset();
+        assert_eq!(session.count(), 0);
+        assert_eq!(session.best(), None);
+        assert_eq!(session.summary(), Summary::default());
+    }
+
+    #[test]
+    fn ser_deser() {
+        let mut session = Session::default();
+        assert_eq!(session.summary().serialise(), vec!["0", "X", "X", "X", "X", "X"]);
+        for t in [time(9500), SolveTime::Dnf, time(10500)]{
+            session.add(t);
+        }
+        let summary = session.summary();
+        assert_eq!(summary.serialise(), vec!["3", "9500", "DNF", "DNF", "X", "X"]);
+        assert_eq!(Summary::deserialise(&summary.serialise()), Ok(summary));
+        assert!(Summary::deserialise(&["1".to_string(), "X".to_string()]).is_err());
+        assert!(Summary::deserialise(&["1", "X", "X", "X", "X", "1.5"].map(|s| s.to_string())).is_err());
+    }
+}
@@ -10,11 +10,14 @@ use clap::Parser as CLIParser;
 use plain_authentic_commands::{MessageHandler, ParseStatus};
 extern crate pest;
 use serde::{Deserialize, Serialize};
-use cube_model::{Cube, Twist};
+use cube_model::{Cube, Twist, Notation};
+use cube_model::catalogue::{self, Entry, Kind};
+use cube_model::cfop::{self, Pll};
 use thiserror::Error;
 use std::time::{Duration};
 
-use game_timer::TimerState;
+use game_timer::{TimerState, Stage, WCA_INSPECTION, MAX_INSPECTION};
+use game_timer::stats::{Session, SolveTime};
 use datapoints::{Datapoint, GameStartDatapoint, TwistDatapoint, GameSolveDatapoint};
 
 use rodio::{Decoder, OutputStream, source::Source, source::Buffered};
@@ -56,10 +59,13 @@ enum StreamEvent{
     GUI(DeviceEvent)
     ,RecvLine(Vec<u8>)
     ,EOS()
-    ,SyncTimers((String, String, String))
+    ,SyncTimers((String, String, String, String, String))
+    ,SyncSplits((String, String, String, String))
     ,ReportTime(Duration)
     ,CubeState(Cube)
     ,RecordState(u128)
+    ,SessionStats(Vec<String>)
+    ,BadArgument(String, String)
 }
 
 enum ClientEvent{
@@ -71,9 +77,11 @@ enum ClientEvent{
     ,UpdateLEDMap(String)
     ,UpdateInputMap(String)
     ,Play()
-    ,StartTimedGame()
+    ,StartTimedGame(Duration)
     ,CancelTimedGame()
     ,SetBrightness(u8)
+    ,ShowNamed(&'static Entry, bool)
+    ,ResetSession()
 }
 
 enum Event{
@@ -113,6 +121,13 @@ fn handle_datapoints(datapoint_receiver: Receiver<Datapoint>, datapoint_secret:
     })
 }
 
+// The states the controller shows while detecting LEDs and inputs, everything is off apart from one white sticker,
+// or a red and a green sticker
+fn is_detection_pattern(state: &str) -> bool {
+    let lit: Vec<char> = state.chars().filter(|c| *c != ' ').collect();
+    state.chars().count() == 54 && lit.len() <= 2 && lit.iter().all(|c| "WRG".contains(*c))
+}
+
 fn handle_stream<R: 'static + Read + Send + Sync, W: 'static + Write + Send + Sync>(read_stream: R, mut write_stream: W, sender: Sender<Event>, secret: Vec<u8>){
     let mut auth = MessageHandler::new(secret);
     let buffer = BufReader::new(read_stream);
@@ -142,8 +157,26 @@ fn handle_stream<R: 'static + Read + Send + Sync, W: 'static + Write + Send + Sy
                                             }
                                             ,"set_state" => {
                                                 if args.len() >= 1{
-                                                    println!("Set absolute cube state: {}", args[0]);
-                                                    sender.send(Event::Client(ClientEvent::SetState(args[0].clone())))?;
+                                                    // The test patterns for detecting LEDs and inputs aren't real cube states, anything else must be reachable
+                                                    let mut cube = Cube::new();
+                                                    let check = match cube.deserialise(&args[0]) {
+                                                        Err(_) => Err("incomplete_state")
+                                                        ,Ok(_) if is_detection_pattern(&args[0]) => Ok(())
+                                                        ,Ok(_) => cube.validate().map_err(|e| {
+                                                            println!("Rejected cube state {}: {}", args[0], e);
+                                                            e.code()
+                                                        })
+                                                    };
+                                                    match check {
+                                                        Ok(_) => {
+                                                            println!("Set absolute cube state: {}", args[0]);
+                                                            sender.send(Event::Client(ClientEvent::SetState(args[0].clone())))?;
+                                                        }
+                                                        ,Err(reason) => {
+                                                            let msg = auth.construct_reply("bad_argument", &vec![&command, reason]);
+                                                            write_stream.write(msg.as_bytes())?;
+                                                        }
+                                                    }
                                                 }
                                                 else{
                                                     let msg = auth.construct_reply("wrong_arguments", &vec![&command]);
@@ -189,7 +222,39 @@ fn handle_stream<R: 'static + Read + Send + Sync, W: 'static + Write + Send + Sy
                                                 sender.send(Event::Client(ClientEvent::Play()))?;
                                             }
                                             ,"timed_start" => {
-                                                sender.send(Event::Client(ClientEvent::StartTimedGame()))?;
+                                                // Optionally the length of inspection in whole seconds, 0 for none, up to a minute
+                                                match args.first().map(|s| u64::from_str(s).map(Duration::from_secs)) {
+                                                    None => {sender.send(Event::Client(ClientEvent::StartTimedGame(WCA_INSPECTION)))?;}
+                                                    ,Some(Ok(inspection)) if inspection <= MAX_INSPECTION => {sender.send(Event::Client(ClientEvent::StartTimedGame(inspection)))?;}
+                                                    ,Some(_) => {
+                                                        let msg = auth.construct_reply("bad_argument", &vec![&command]);
+                                                        write_stream.write(msg.as_bytes())?;
+                                                    }
+                                                }
+                                            }
+                                            ,"reset_session" => {
+                                                // Starts the session stats again, for when someone new comes along
+                                                sender.send(Event::Client(ClientEvent::ResetSession()))?;
+                                            }
+                                            ,"pattern" => {
+                                                // pattern:<name>, sets the state straight away, or pattern:<name>,animate, to watch the twists being done
+                                                let animate = args.len() == 2 && args[1] == "animate";
+                                                if args.len() == 1 || animate{
+                                                    match catalogue::lookup(&args[0]) {
+                                                        Some(entry) => {
+                                                            println!("Show {}: {}", entry.name, entry.twists);
+                                                            sender.send(Event::Client(ClientEvent::ShowNamed(entry, animate)))?;
+                                                        }
+                                                        ,None => {
+                                                            let msg = auth.construct_reply("bad_argument", &vec![&command, "unknown_pattern"]);
+                                                            write_stream.write(msg.as_bytes())?;
+                                                        }
+                                                    }
+                                                }
+                                                else{
+                                                    let msg = auth.construct_reply("wrong_arguments", &vec![&command]);
+                                                    write_stream.write(msg.as_bytes())?;
+                                                }
                                             }
                                             ,"cancel_timer" => {
                                                 sender.send(Event::Client(ClientEvent::CancelTimedGame()))?;
@@ -240,8 +305,13 @@ fn handle_stream<R: 'static + Read + Send + Sync, W: 'static + Write + Send + Sy
                                 }
                                 Ok(Loop)
                             }
-                            ,SyncTimers((a,b,c)) => {
-                                let msg = auth.construct_reply("timer_state", &vec![&a,&b,&c]);
+                            ,SyncTimers((a,b,c,d,e)) => {
+                                let msg = auth.construct_reply("timer_state", &vec![&a,&b,&c,&d,&e]);
+                                write_stream.write(msg.as_bytes())?;
+                                Ok(Loop)
+                            }
+                            ,SyncSplits((a,b,c,d)) => {
+                                let msg = auth.construct_reply("splits", &vec![&a,&b,&c,&d]);
                                 write_stream.write(msg.as_bytes())?;
                                 Ok(Loop)
                             }
@@ -260,6 +330,16 @@ fn handle_stream<R: 'static + Read + Send + Sync, W: 'static + Write + Send + Sy
                                 write_stream.write(msg.as_bytes())?;
                                 Ok(Loop)
                             }
+                            ,SessionStats(stats) => {
+                                let msg = auth.construct_reply("session_stats", &stats.iter().map(|s| s.as_str()).collect());
+                                write_stream.write(msg.as_bytes())?;
+                                Ok(Loop)
+                            }
+                            ,BadArgument(command, reason) => {
+                                let msg = auth.construct_reply("bad_argument", &vec![&command, &reason]);
+                                write_stream.write(msg.as_bytes())?;
+                                Ok(Loop)
+                            }
                         }
                     })();
                     match r {
@@ -306,14 +386,35 @@ fn persist_config(config: &CubeConfig, file: &str) {
     }
 }
 
-fn send_state_to_client(gui_sender: Option<&Sender<StreamEvent>>, cube: Cube, record: u128) -> Result<(), SendError<StreamEvent>>{
+fn send_state_to_client(gui_sender: Option<&Sender<StreamEvent>>, cube: Cube, record: u128, session: &Session) -> Result<(), SendError<StreamEvent>>{
     if let Some(sender) = gui_sender {
         sender.send(StreamEvent::CubeState(cube))?;
         sender.send(StreamEvent::RecordState(record))?;
+        sender.send(StreamEvent::SessionStats(session.summary().serialise()))?;
     }
     Ok(())
 }
 
+// The furthest CFOP stage that this cube has done
+fn completed_stage(cube: &Cube) -> Option<Stage>{
+    let progress = cfop::progress(cube);
+    if progress.pll == Some(Pll::Solved) {
+        Some(Stage::Pll)
+    }
+    else if progress.oll == Some(0) {
+        Some(Stage::Oll)
+    }
+    else if progress.f2l_pairs == 4 {
+        Some(Stage::F2l)
+    }
+    else if progress.cross.is_some() {
+        Some(Stage::Cross)
+    }
+    else {
+        None
+    }
+}
+
 fn main() {
     println!("Cube service");
 
@@ -431,7 +532,7 @@ fn main() {
                                 ,(ParseTwist, b';') => {
                                     // end of twist
                                     mode = Normal;
-                                    if let Ok(t) = Twist::from_bytes(&twist_id){
+                                    if let Ok(t) = Twist::from_bytes_in(&twist_id, Notation::Legacy){
                                         println!("Twist: {}", t);
                                         dev_sender.send(Event::Device(DeviceEvent::Twist(t)))?;
                                     }
@@ -553,6 +654,9 @@ fn main() {
 
     let mut game_state = TimerState::default();
 
+    // Every solve since the service started
+    let mut session = Session::default();
+
 
     for event in receiver.iter(){
         match event {
@@ -606,11 +710,12 @@ fn main() {
                             device_write.write(b"p")?;
                             device_write.flush()?;
                         }
-                        ,ClientEvent::StartTimedGame() => {
+                        ,ClientEvent::StartTimedGame(inspection) => {
                             game_state.reset();
-                            game_state.start();
+                            game_state.start_with_inspection(inspection);
                             if let Some(sender) = gui_sender.as_ref(){
                                 sender.send(StreamEvent::SyncTimers(game_state.serialise()))?;
+                                sender.send(StreamEvent::SyncSplits(game_state.serialise_splits()))?;
                             }
                             let _ = datapoints_sender.try_send(Datapoint::GameStart(GameStartDatapoint {
                                 game_id: game_state.game_id().unwrap().to_string(),
@@ -622,19 +727,57 @@ fn main() {
                             game_state.reset();
                             if let Some(sender) = gui_sender.as_ref(){
                                 sender.send(StreamEvent::SyncTimers(game_state.serialise()))?;
+                                sender.send(StreamEvent::SyncSplits(game_state.serialise_splits()))?;
                             }
                         }
                         ,ClientEvent::Connected(sender) => {
                             gui_sender = Some(sender);
                         }
                         ,ClientEvent::GetState() => {
-                            send_state_to_client(gui_sender.as_ref(), cube, config.top_score)?;
+                            send_state_to_client(gui_sender.as_ref(), cube, config.top_score, &session)?;
                         }
                         ,ClientEvent::SetBrightness(b) => {
                             device_write.write(b"%")?;
                             device_write.write(&[b])?;
                             device_write.flush()?;
                         }
+                        ,ClientEvent::ResetSession() => {
+                            session.reset();
+                            if let Some(sender) = gui_sender.as_ref(){
+                                sender.send(StreamEvent::SessionStats(session.summary().serialise()))?;
+                            }
+                        }
+                        ,ClientEvent::ShowNamed(entry, animate) => {
+                            // Showing a pattern would mess up a timed game, the command thread doesn't know about
+                            // games so the refusal is sent from here
+                            if game_state.is_started() && !game_state.is_ended() {
+                                if let Some(sender) = gui_sender.as_ref(){
+                                    sender.send(StreamEvent::BadArgument("pattern".to_string(), "game_in_progress".to_string()))?;
+                                }
+                                return Ok(());
+                            }
+                            // Patterns are made from solved, algorithms are done from wherever the cube is
+                            if entry.kind == Kind::Pattern && !(animate && cube.is_solved()) {
+                                cube = Cube::new();
+                                if animate {
+                                    device_write.write(b"u")?;
+                                    device_write.write(cube.serialise().as_bytes())?;
+                                }
+                            }
+                            // Everything in the catalogue is checked by its tests, so this can't fail
+                            cube.twists_in(entry.twists, Notation::Standard).expect("catalogue twists are valid");
+                            if animate {
+                                // The device does the twists itself, one after another
+                                device_write.write(b"s")?;
+                                device_write.write(entry.twists.as_bytes())?;
+                                device_write.write(b";")?;
+                            }
+                            else {
+                                device_write.write(b"u")?;
+                                device_write.write(cube.serialise().as_bytes())?;
+                            }
+                            device_write.flush()?;
+                        }
                     }
                     Ok(())
                 })(c_ev);
@@ -654,6 +797,16 @@ fn main() {
                         }
                         let _ignored = sound_sender.send(Sound::Twist());
                         cube.twist(twist);
+                        if game_state.is_started() && !game_state.is_ended() {
+                            if let Some(stage) = completed_stage(&cube) {
+                                if game_state.stage_done(stage) {
+                                    if let Some(sender) = gui_sender.as_ref(){
+                                        // Best-effort like the timer sync
+                                        let _ignored = sender.send(StreamEvent::SyncSplits(game_state.serialise_splits()));
+                                    }
+                                }
+                            }
+                        }
 
                         let mut game_id = None;
                         let mut play_time_milliseconds = None;
@@ -674,28 +827,43 @@ fn main() {
                         if let Some(sender) = gui_sender.as_ref(){
                             // Timer syc events are best-effort, ignore errors
                             let _ignored = sender.send(StreamEvent::SyncTimers(game_state.serialise()));
+                            let _ignored = sender.send(StreamEvent::SyncSplits(game_state.serialise_splits()));
                         }
                         if is_win{
                             let _ignored = sound_sender.send(Sound::Win());
-                            match game_state.recorded_time(){
-                                Some(time) => {
+                            match (game_state.solve_time(), game_state.penalty()){
+                                (Some(solve_time), Some(penalty)) => {
+                                    session.add(SolveTime::from_recorded(game_state.recorded_time()));
                                     if let Some(sender) = gui_sender.as_ref(){
-                                        // TODO do I even need this event??
-                                        let _ignored = sender.send(StreamEvent::ReportTime(time));
+                                        let _ignored = sender.send(StreamEvent::SessionStats(session.summary().serialise()));
                                     }
-                                    let t = time.as_millis();
                                     let mut new_top_score = false;
-                                    if (config.top_score == 0) || (t < config.top_score){
-                                        new_top_score = true;
-                                        config.top_score = t;
-                                        persist_config(&config, &args.config);
+                                    // A DNF has no recorded time, so it can't be a top score
+                                    if let Some(time) = game_state.recorded_time(){
                                         if let Some(sender) = gui_sender.as_ref(){
-                                            let _ignored = sender.send(StreamEvent::RecordState(t));
+                                            // TODO do I even need this event??
+                                            let _ignored = sender.send(StreamEvent::ReportTime(time));
+                                        }
+                                        let t = time.as_millis();
+                                        if (config.top_score == 0) || (t < config.top_score){
+                                            new_top_score = true;
+                                            config.top_score = t;
+                                            persist_config(&config, &args.config);
+                                            if let Some(sender) = gui_sender.as_ref(){
+                                                let _ignored = sender.send(StreamEvent::RecordState(t));
+                                            }
                                         }
                                     }
+                                    let [cross, f2l, oll, pll] = game_state.splits().map(|s| s.and_then(|s| s.as_millis().try_into().ok()));
                                     let _ = datapoints_sender.try_send(Datapoint::GameSolve(GameSolveDatapoint {
                                         game_id: game_state.game_id().unwrap().to_string(),
-                                        play_time_milliseconds: t.try_into().unwrap_or(u32::MAX),
+                                        play_time_milliseconds: game_state.recorded_time().map(|t| t.as_millis().try_into().unwrap_or(u32::MAX)),
+                                        raw_play_time_milliseconds: solve_time.as_millis().try_into().unwrap_or(u32::MAX),
+                                        penalty: penalty.to_string(),
+                                        cross_milliseconds: cross,
+                                        f2l_milliseconds: f2l,
+                                        oll_milliseconds: oll,
+                                        pll_milliseconds: pll,
                                         new_top_score,
                                         cube_state: cube.serialise(),
                                         timestamp: Utc::now(),